@@ -1,64 +1,93 @@
+use crate::autosave::{
+    end_session, start_session, AutoSaves, Interval, Schedule, Snapshot, SnapshotStore,
+};
 use crate::bot::Direction;
-use crate::images::Images;
+use crate::camera::Lineage;
+use crate::colony::Links;
+use crate::commands::{Bindings, Command};
+use crate::config::{ConfigError, Format, Preset, SimConfig};
+use crate::consts::{autosave, drawing, history, timeline};
+use crate::editor::{rect_between, Brush, Edit, Editor, Shape};
+use crate::export::{self, TimeLapse};
+use crate::history::History;
+use crate::images::{Atlas, CellSize, Images, Tile};
+use crate::map::{Map, Object, Terrain};
+use crate::render::{self, RenderMode, TileMesh};
+use crate::replay::{Replay, WorldReplay};
+use crate::settings;
+#[cfg(target_arch = "wasm32")]
+use crate::share::ShareLink;
+use crate::timeline::Timeline;
+use crate::world::{new_seeded_rand, BotId, World};
+use crate::{camera, Point};
 use base64::engine::general_purpose;
-use core::hash;
-use egui::{Color32, Image, Sense, Style, TextureHandle, TextureOptions, Visuals};
-use rand::{Fill, Rng, SeedableRng};
-use rand_seeder::SipHasher;
+use egui::{
+    vec2, Color32, DragValue, Event, Image, ImageButton, Key, KeyboardShortcut, Pos2, Sense,
+    Stroke, Style, TextureHandle, TextureOptions, Vec2, Visuals,
+};
+use rand::Rng;
 use rand_xoshiro::Xoshiro256PlusPlus;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::fmt;
-use std::fmt::{Debug, Display, Formatter};
-use std::io::Write;
-use std::time::Duration;
+use std::fmt::Debug;
+use std::fs;
+use std::io::{self, Write};
+use std::time::SystemTime;
 use web_time::Instant;
 
-#[derive(Eq, PartialEq, Clone)]
-struct BotTextures {
-    head: [TextureHandle; 8],
-    body: TextureHandle,
-}
-
 #[derive(Eq, PartialEq, Clone)]
 struct ImageTextures {
-    apple: TextureHandle,
-    organics: TextureHandle,
-    rock: TextureHandle,
-    bot: BotTextures,
+    cell_size: CellSize,
+    atlas: Atlas,
+    texture: TextureHandle,
 }
 
-#[derive(Deserialize, Serialize, Eq, PartialEq, Copy, Clone, Debug)]
-enum AutoSaveSec {
-    One,
-    Five,
-    Fifteen,
+impl ImageTextures {
+    fn image(&self, tile: Tile) -> Image<'static> {
+        let size = self.atlas.cell_size as f32;
+        Image::new((self.texture.id(), vec2(size, size))).uv(self.atlas.uv(tile))
+    }
 }
 
-impl AutoSaveSec {
-    fn duration(&self) -> Duration {
-        match self {
-            AutoSaveSec::One => Duration::from_secs(1),
-            AutoSaveSec::Five => Duration::from_secs(5),
-            AutoSaveSec::Fifteen => Duration::from_secs(15),
-        }
+fn auto_save_to_str(schedule: Option<Schedule>) -> Cow<'static, str> {
+    match schedule {
+        None => Cow::from("Disabled"),
+        Some(schedule) => Cow::from(schedule.interval.to_string()),
     }
 }
 
-impl Display for AutoSaveSec {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.duration())
-    }
+/// Texture coordinates of a whole texture
+const FULL_UV: egui::Rect = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+
+#[derive(Deserialize, Serialize, PartialEq, Default)]
+#[serde(default)]
+struct ConfigEditor {
+    format: Format,
+    #[serde(skip)]
+    text: String,
+    #[serde(skip)]
+    error: Option<ConfigError>,
+    /// Edited rules, applied once they are valid
+    #[serde(skip)]
+    draft: Option<SimConfig>,
 }
 
-fn auto_save_to_str(auto_save_sec: Option<AutoSaveSec>) -> Cow<'static, str> {
-    match auto_save_sec {
-        None => Cow::from("Disabled"),
-        Some(auto_save) => Cow::from(auto_save.to_string()),
-    }
+/// Undoable change of the app state.
+#[derive(Clone, PartialEq, Debug)]
+enum Action {
+    Edit(Edit),
+    Config {
+        before: SimConfig,
+        after: SimConfig,
+    },
+    /// World moved from tick `from` to `to` by steps or along the timeline
+    Steps {
+        from: u64,
+        to: u64,
+    },
 }
 
-#[derive(Deserialize, Serialize, Eq, PartialEq)]
+#[derive(Deserialize, Serialize, PartialEq)]
 #[serde(default)]
 pub struct TemplateApp {
     #[serde(skip)]
@@ -70,9 +99,84 @@ pub struct TemplateApp {
     rng: Xoshiro256PlusPlus,
     is_dark: bool,
     show_settings: bool,
-    auto_save: Option<AutoSaveSec>,
+    auto_save_schedule: Option<Schedule>,
     #[serde(skip)]
     last_save: Instant,
+    /// Tick of the world at the last save
+    #[serde(skip)]
+    last_save_tick: u64,
+    /// Whether a population event of the schedule asks for a save
+    #[serde(skip)]
+    save_triggered: bool,
+    sim_config: SimConfig,
+    config_editor: ConfigEditor,
+    cell_size: CellSize,
+    world: World,
+    /// Whether the world is simulated every frame
+    #[serde(skip)]
+    running: bool,
+    /// Ticks simulated by one step command
+    step_ticks: u64,
+    /// Snapshots of the world to undo steps from
+    #[serde(skip)]
+    timeline: Timeline<World>,
+    /// Tick since which the world was moved without recording it in the history
+    #[serde(skip)]
+    steps_from: Option<u64>,
+    editor: Editor,
+    edit_mode: bool,
+    #[serde(skip)]
+    history: History<Action>,
+    /// Config at the last recorded action, changes are recorded once the pointer is released
+    #[serde(skip)]
+    committed_config: Option<SimConfig>,
+    /// Whether the last session was checked for a crash
+    #[serde(skip)]
+    session_started: bool,
+    /// Whether the user closes the app, so the next save is the last one
+    #[serde(skip)]
+    closing: bool,
+    /// Loaded on the first frame, when the app storage is available
+    #[serde(skip)]
+    autosaves: Option<AutoSaves>,
+    /// Snapshot offered to restore after a crash
+    #[serde(skip)]
+    restore: Option<Snapshot>,
+    bindings: Bindings,
+    /// Filter text of the open command palette
+    #[serde(skip)]
+    palette: Option<String>,
+    /// Command waiting for a key press to bind
+    #[serde(skip)]
+    rebinding: Option<Command>,
+    #[serde(skip)]
+    selected: Option<BotId>,
+    /// Bot kept in the middle of the field view
+    #[serde(skip)]
+    followed: Option<BotId>,
+    /// Message shown above the field until dismissed
+    #[serde(skip)]
+    notice: Option<String>,
+    show_minimap: bool,
+    #[serde(skip)]
+    minimap: Option<TextureHandle>,
+    /// Field texture for small cells, see [`RenderMode::Pixels`]
+    #[serde(skip)]
+    field_pixels: Option<TextureHandle>,
+    /// Scroll offset and size of the field view in the last frame
+    #[serde(skip)]
+    view: Option<(Vec2, Vec2)>,
+    /// Scroll offset requested from the minimap
+    #[serde(skip)]
+    jump_to: Option<Vec2>,
+    /// Ticks between time-lapse frames
+    time_lapse_interval: u64,
+    /// Recorder of the running time-lapse
+    #[serde(skip)]
+    time_lapse: Option<TimeLapse>,
+    /// Run being recorded since the world was last replaced, rewound or its rules changed
+    #[serde(skip)]
+    replay: Option<WorldReplay>,
 }
 
 impl Default for TemplateApp {
@@ -87,8 +191,40 @@ impl Default for TemplateApp {
             initial_seed,
             is_dark: Default::default(),
             show_settings: Default::default(),
-            auto_save: None,
+            auto_save_schedule: None,
             last_save: Instant::now(),
+            last_save_tick: 0,
+            save_triggered: false,
+            sim_config: Default::default(),
+            config_editor: Default::default(),
+            cell_size: Default::default(),
+            world: Default::default(),
+            running: false,
+            step_ticks: 1,
+            timeline: Timeline::new(timeline::CHECKPOINT_INTERVAL, timeline::MAX_CHECKPOINTS),
+            steps_from: None,
+            editor: Default::default(),
+            edit_mode: false,
+            history: History::new(history::MAX_ACTIONS),
+            committed_config: None,
+            session_started: false,
+            closing: false,
+            autosaves: None,
+            restore: None,
+            bindings: Default::default(),
+            palette: None,
+            rebinding: None,
+            selected: None,
+            followed: None,
+            notice: None,
+            show_minimap: false,
+            minimap: None,
+            field_pixels: None,
+            time_lapse_interval: 10,
+            time_lapse: None,
+            replay: None,
+            view: None,
+            jump_to: None,
         }
     }
 }
@@ -100,11 +236,34 @@ impl TemplateApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        let result: TemplateApp = if let Some(storage) = cc.storage {
-            eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default()
-        } else {
-            Default::default()
+        let mut result: TemplateApp = match cc.storage {
+            Some(storage) => match storage.get_string(settings::KEY) {
+                Some(text) => settings::from_str(&text),
+                // Saved before settings were versioned
+                None => storage
+                    .get_string(eframe::APP_KEY)
+                    .map(|text| settings::from_legacy_ron(&text))
+                    .unwrap_or_default(),
+            },
+            None => Default::default(),
         };
+        if let Err(e) = result.sim_config.validate() {
+            log::warn!("Stored simulation rules are replaced by defaults: {e}");
+            result.sim_config = Default::default();
+        }
+        #[cfg(target_arch = "wasm32")]
+        crate::autosave::mark_crash_on_panic();
+        // Parameters from a shared link take precedence over the stored ones.
+        #[cfg(target_arch = "wasm32")]
+        if let Some((link, error)) =
+            ShareLink::from_fragment(&cc.integration_info.web_info.location.hash)
+        {
+            result.apply_share_link(link);
+            if let Some(e) = error {
+                result.notice = Some(format!("Rules of the link are not applied: {e}"));
+            }
+        }
+        result.reset_history();
         cc.egui_ctx.set_visuals(if result.is_dark {
             Visuals::dark()
         } else {
@@ -114,45 +273,257 @@ impl TemplateApp {
     }
 
     fn images(&mut self, ui: &'_ mut egui::Ui) -> &ImageTextures {
+        let cell_size = self.cell_size;
+        // Textures are regenerated after cell size change
+        if self
+            .images
+            .as_ref()
+            .is_some_and(|images| images.cell_size != cell_size)
+        {
+            self.images = None;
+        }
         self.images.get_or_insert_with(|| {
-            let images = Images::default();
+            let images = Images::with_cell_size(cell_size);
+            let atlas = Atlas {
+                cell_size: cell_size.px(),
+            };
             ImageTextures {
-                apple: ui
-                    .ctx()
-                    .load_texture("apple", images.apple, TextureOptions::default()),
-                organics: ui.ctx().load_texture(
-                    "organics",
-                    images.organics,
-                    TextureOptions::default(),
+                cell_size,
+                atlas,
+                // Nearest filtering keeps neighbour tiles from bleeding into each other
+                texture: ui.ctx().load_texture(
+                    "atlas",
+                    atlas.pack(&images),
+                    TextureOptions::NEAREST,
                 ),
-                rock: ui
-                    .ctx()
-                    .load_texture("rock", images.rock, TextureOptions::default()),
-                bot: BotTextures {
-                    head: images.bot.head.map(|image| {
-                        ui.ctx()
-                            .load_texture("bot-head", image, TextureOptions::default())
-                    }),
-                    body: ui.ctx().load_texture(
-                        "bot-body",
-                        images.bot.body,
-                        TextureOptions::default(),
-                    ),
-                },
             }
         })
     }
 
     fn auto_save(&mut self, frame: &mut eframe::Frame) {
         if let Some(storage) = frame.storage_mut() {
-            if let Some(auto_save) = self.auto_save {
-                if self.last_save.elapsed() >= auto_save.duration() {
+            self.load_autosaves(storage);
+            if let Some(schedule) = self.auto_save_schedule {
+                let ticks = self.world.tick().saturating_sub(self.last_save_tick);
+                if self.save_triggered || schedule.is_due(self.last_save.elapsed(), ticks) {
                     self.save(storage);
+                    self.save_snapshot(storage);
                 }
             }
         }
     }
 
+    /// Checks the last session for a crash and loads the auto-saves on the first frame
+    fn load_autosaves(&mut self, storage: &mut dyn eframe::Storage) {
+        if self.session_started {
+            return;
+        }
+        self.session_started = true;
+        let crashed = start_session(storage) && self.auto_save_schedule.is_some();
+        let Some((autosaves, latest)) = with_snapshot_store(storage, |store| {
+            (
+                AutoSaves::resume(store, autosave::SLOTS),
+                AutoSaves::latest(store, autosave::SLOTS),
+            )
+        }) else {
+            log::warn!("No place to auto-save the world");
+            return;
+        };
+        self.autosaves = Some(autosaves);
+        if crashed {
+            self.restore = latest;
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            initial_seed: self.initial_seed.clone(),
+            sim_config: self.sim_config,
+            world: self.world.clone(),
+        }
+    }
+
+    fn save_snapshot(&mut self, storage: &mut dyn eframe::Storage) {
+        let snapshot = self.snapshot();
+        let Some(autosaves) = &mut self.autosaves else {
+            return;
+        };
+        if let Some(Err(e)) = with_snapshot_store(storage, |store| autosaves.save(store, &snapshot))
+        {
+            log::error!("Failed to auto-save the world: {e}");
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        self.initial_seed = snapshot.initial_seed;
+        self.rng = new_seeded_rand(&self.initial_seed);
+        self.sim_config = snapshot.sim_config;
+        self.committed_config = None;
+        self.world = snapshot.world;
+        self.selected = None;
+        self.followed = None;
+        self.reset_history();
+    }
+
+    /// Starts the history anew from the current world
+    fn reset_history(&mut self) {
+        self.end_replay(Some("the world was replaced"));
+        self.history = History::new(history::MAX_ACTIONS);
+        self.timeline.restart(self.world.tick(), &self.world);
+        self.steps_from = None;
+    }
+
+    /// Records an action, after the steps made before it
+    fn push(&mut self, action: Action) {
+        self.end_steps();
+        self.history.push(action);
+    }
+
+    /// Records the moves made since the last action as one action
+    fn end_steps(&mut self) {
+        if let Some(from) = self.steps_from.take() {
+            let to = self.world.tick();
+            if to != from {
+                self.history.push(Action::Steps { from, to });
+            }
+        }
+    }
+
+    /// The world or the rules changed at the current tick, so the recorded future is void
+    fn changed(&mut self) {
+        self.timeline.replace(self.world.tick(), &self.world);
+    }
+
+    /// Checkpoints don't keep the rules, so the ticks before a rules change can't be
+    /// simulated again and the timeline starts at the change
+    fn rules_changed(&mut self) {
+        self.timeline.restart(self.world.tick(), &self.world);
+    }
+
+    /// Steps the world forward to `tick`, or rewinds it from the nearest checkpoint.
+    /// Returns `false` if `tick` is before the first checkpoint.
+    fn go_to(&mut self, tick: u64) -> bool {
+        if tick < self.world.tick() {
+            self.end_replay(Some("the world was rewound"));
+            let config = self.sim_config;
+            match self.timeline.rewind(tick, |world| world.step(&config)) {
+                Some(world) => self.world = world,
+                None => return false,
+            }
+        }
+        while self.world.tick() < tick {
+            self.tick();
+        }
+        true
+    }
+
+    /// Slider over the ticks which can be reconstructed, re-simulating from checkpoints
+    fn timeline_panel(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::bottom("timeline").show(ctx, |ui| {
+            let mut tick = self.world.tick();
+            let first = self.timeline.first_tick().unwrap_or(tick);
+            let last = self.timeline.last_tick().unwrap_or(tick).max(tick);
+            ui.spacing_mut().slider_width = ui.available_width() - 80.0;
+            let response = ui
+                .add(egui::Slider::new(&mut tick, first..=last).text("Tick"))
+                .on_hover_text("Return to a past tick, the last ones are simulated again");
+            if response.changed() {
+                if self.running {
+                    self.running = false;
+                    self.end_steps();
+                }
+                self.steps_from.get_or_insert(self.world.tick());
+                self.go_to(tick);
+            }
+            // One undoable action per drag
+            if response.drag_stopped() || response.changed() && !response.dragged() {
+                self.end_steps();
+            }
+        });
+    }
+
+    /// Simulates one tick, keeping it for rewinding and replays
+    fn tick(&mut self) {
+        let population = |world: &World| u32::try_from(world.population()).unwrap_or(u32::MAX);
+        let before = population(&self.world);
+        self.world.step(&self.sim_config);
+        if let Some(schedule) = self.auto_save_schedule {
+            self.save_triggered |= schedule.is_triggered(before, population(&self.world));
+        }
+        self.timeline.record(self.world.tick(), &self.world);
+        if let Some(replay) = &mut self.replay {
+            replay.record_tick(&self.world);
+        }
+    }
+
+    /// Applies `f` to the map with the editor, recording the changed cells in the replay
+    fn edit_map<R>(&mut self, f: impl FnOnce(&mut Map, &mut Editor) -> R) -> R {
+        let editor = &mut self.editor;
+        let (result, edit) = self.world.edit(&self.sim_config, |map| {
+            Edit::track(map, |map| f(map, editor))
+        });
+        if let Some(replay) = &mut self.replay {
+            if !edit.is_empty() {
+                replay.record_event(edit);
+            }
+        }
+        result
+    }
+
+    fn step(&mut self) {
+        self.steps_from.get_or_insert(self.world.tick());
+        self.tick();
+        if let Some(time_lapse) = &mut self.time_lapse {
+            let (world, cell_size) = (&self.world, self.cell_size);
+            let recorded = time_lapse.record(world.tick(), || {
+                render::sprite_image(&Images::with_cell_size(cell_size), world.map())
+            });
+            if let Err(e) = recorded {
+                log::error!("Time-lapse stopped: {e}");
+                self.time_lapse = None;
+            }
+        }
+    }
+
+    /// Generates a new world by the rules, with a new seed unless it is locked
+    fn new_world(&mut self) {
+        if !self.lock_initial_seed {
+            self.initial_seed = generate_initial_seed();
+        }
+        self.rng = new_seeded_rand(&self.initial_seed);
+        self.world = World::generate(
+            self.world.map().size(),
+            &self.sim_config,
+            new_seeded_rand(&self.initial_seed),
+        );
+        self.selected = None;
+        self.followed = None;
+        self.reset_history();
+    }
+
+    fn restore_window(&mut self, ctx: &egui::Context) {
+        if self.restore.is_none() {
+            return;
+        }
+        egui::Window::new("Restore last session")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("The previous session was not closed properly.");
+                ui.label("Restore the last auto-saved world?");
+                ui.horizontal(|ui| {
+                    if ui.button("Restore").clicked() {
+                        if let Some(snapshot) = self.restore.take() {
+                            self.restore_snapshot(snapshot);
+                        }
+                    }
+                    if ui.button("Discard").clicked() {
+                        self.restore = None;
+                    }
+                });
+            });
+    }
+
     fn top_menu(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         self.auto_save(frame);
 
@@ -163,12 +534,38 @@ impl TemplateApp {
 
                 let is_web = cfg!(target_arch = "wasm32");
                 ui.menu_button("File", |ui| {
+                    if ui.button("New world").clicked() {
+                        self.new_world();
+                        ui.close_menu();
+                    }
                     if let Some(storage) = frame.storage_mut() {
                         if ui.button("Save").clicked() {
                             self.save(storage);
                             ui.close_menu();
                         }
                     }
+                    #[cfg(target_arch = "wasm32")]
+                    if ui
+                        .button("Copy link")
+                        .on_hover_text("Copy link to reproduce this simulation")
+                        .clicked()
+                    {
+                        ctx.copy_text(self.share_url(frame));
+                        ui.close_menu();
+                    }
+                    // NOTE: no file system on web pages
+                    if !is_web {
+                        ui.menu_button("Export image", |ui| {
+                            for cell_size in CellSize::ALL {
+                                if ui.button(cell_size.to_string()).clicked() {
+                                    self.export_image(cell_size);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        self.time_lapse_menu(ui);
+                        self.replay_menu(ui);
+                    }
                     // NOTE: no File->Quit on web pages
                     if !is_web {
                         ui.separator();
@@ -178,9 +575,46 @@ impl TemplateApp {
                         }
                     }
                 });
+                ui.menu_button("Edit", |ui| {
+                    for command in [Command::Undo, Command::Redo, Command::CommandPalette] {
+                        let button = egui::Button::new(command.to_string())
+                            .shortcut_text(self.shortcut_text(ctx, command));
+                        if ui.add_enabled(self.is_available(command), button).clicked() {
+                            self.run(command, ctx, frame);
+                            ui.close_menu();
+                        }
+                    }
+                });
                 if ui.button("Settings").clicked() {
                     self.show_settings ^= true;
                 }
+                ui.toggle_value(&mut self.edit_mode, "Edit map");
+                ui.toggle_value(&mut self.show_minimap, "Minimap");
+                ui.separator();
+                for command in [Command::PlayPause, Command::Step] {
+                    let text = match command {
+                        Command::PlayPause if self.running => "⏸".to_string(),
+                        Command::PlayPause => "▶".to_string(),
+                        _ => command.to_string(),
+                    };
+                    let hover = format!("{command} {}", self.shortcut_text(ctx, command));
+                    if ui.button(text).on_hover_text(hover.trim_end()).clicked() {
+                        self.run(command, ctx, frame);
+                    }
+                }
+                ui.add(egui::DragValue::new(&mut self.step_ticks).range(1..=10_000))
+                    .on_hover_text("Ticks per step");
+                let transfers = self.world.transfers();
+                ui.label(format!(
+                    "Tick {}, {} bots, {} transfers",
+                    self.world.tick(),
+                    self.world.population(),
+                    transfers.total()
+                ))
+                .on_hover_text(format!(
+                    "Last tick: {} infections, {} conjugations",
+                    transfers.infections, transfers.conjugations
+                ));
             });
         });
     }
@@ -208,55 +642,650 @@ impl TemplateApp {
                     }
                 });
                 ui.collapsing(
-                    format!("Auto save: {}", auto_save_to_str(self.auto_save)),
-                    |ui| {
-                        let mut radio_value = |auto_save| {
-                            ui.radio_value(
-                                &mut self.auto_save,
-                                auto_save,
-                                auto_save_to_str(auto_save),
-                            );
-                        };
-                        radio_value(None);
-                        radio_value(Some(AutoSaveSec::One));
-                        radio_value(Some(AutoSaveSec::Five));
-                        radio_value(Some(AutoSaveSec::Fifteen));
+                    format!("Auto save: {}", auto_save_to_str(self.auto_save_schedule)),
+                    |ui| auto_save_editor(ui, &mut self.auto_save_schedule),
+                );
+                ui.collapsing(format!("Cell size: {}", self.cell_size), |ui| {
+                    for cell_size in CellSize::ALL {
+                        ui.radio_value(&mut self.cell_size, cell_size, cell_size.to_string());
+                    }
+                });
+                ui.collapsing("Simulation rules", |ui| {
+                    sim_config_editor(ui, &mut self.sim_config, &mut self.config_editor);
+                });
+                ui.collapsing("Keyboard shortcuts", |ui| {
+                    bindings_editor(ui, &mut self.bindings, &mut self.rebinding);
+                });
+            });
+    }
+
+    fn tool_palette(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::left("tool_palette").show(ctx, |ui| {
+            let images = self.images(ui).clone();
+            let selected = self.editor.brush;
+            let mut brush = selected;
+            ui.label("Objects:");
+            ui.horizontal_wrapped(|ui| {
+                for object in [
+                    Object::Rock,
+                    Object::Apple,
+                    Object::Organics,
+                    Object::Bot {
+                        head: self.bot_head,
+                        color: self.bot_color,
+                        links: Links::default(),
                     },
+                ] {
+                    let is_selected = match selected {
+                        Brush::Object(selected) => {
+                            std::mem::discriminant(&selected) == std::mem::discriminant(&object)
+                        }
+                        _ => false,
+                    };
+                    let (tile, tint) = object.tiles()[0];
+                    let image = images
+                        .image(tile)
+                        .tint(tint)
+                        .fit_to_exact_size(vec2(24.0, 24.0));
+                    let button = ui.add(ImageButton::new(image).selected(is_selected));
+                    if button.clicked() {
+                        brush = Brush::Object(object);
+                    }
+                }
+            });
+            ui.label("Terrain:");
+            ui.horizontal_wrapped(|ui| {
+                for terrain in Terrain::ALL {
+                    let button = ui.add(
+                        egui::Button::new(terrain.to_string())
+                            .fill(terrain.color())
+                            .selected(selected == Brush::Terrain(terrain)),
+                    );
+                    if button.clicked() {
+                        brush = Brush::Terrain(terrain);
+                    }
+                }
+            });
+            let button = ui.add(egui::Button::new("Eraser").selected(selected == Brush::Eraser));
+            if button.clicked() {
+                brush = Brush::Eraser;
+            }
+            self.editor.brush = brush;
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.editor.shape, Shape::Free, "Free");
+                ui.radio_value(&mut self.editor.shape, Shape::Rect, "Rectangle");
+            });
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+                    .clicked()
+                {
+                    self.redo();
+                }
+            });
+        });
+    }
+
+    fn field_view(&mut self, ui: &mut egui::Ui) {
+        let images = self.images(ui).clone();
+        let cell_size = images.atlas.cell_size as f32;
+        let size = self.world.map().size();
+        let mut scroll_area = egui::ScrollArea::both();
+        if let Some(followed) = self.followed {
+            match camera::follow(&self.world, followed) {
+                Some((bot, cell)) => {
+                    if bot != followed {
+                        self.notice = Some("Following a descendant of the bot".to_string());
+                    }
+                    self.followed = Some(bot);
+                    self.selected = Some(bot);
+                    scroll_area = scroll_area.scroll_offset(camera::center_offset(
+                        cell,
+                        cell_size,
+                        ui.available_size(),
+                    ));
+                }
+                None => {
+                    self.followed = None;
+                    self.selected = None;
+                    self.notice = Some("The followed bot has no living descendants".to_string());
+                }
+            }
+        }
+        if let Some(offset) = self.jump_to.take() {
+            scroll_area = scroll_area.scroll_offset(offset);
+        }
+        let output = scroll_area.show(ui, |ui| {
+            let sense = if self.edit_mode {
+                Sense::click_and_drag()
+            } else {
+                Sense::click()
+            };
+            let (rect, response) =
+                ui.allocate_exact_size(vec2(size.w as f32, size.h as f32) * cell_size, sense);
+            let painter = ui.painter_at(rect);
+            let cell_rect = |point: Point<usize>| {
+                egui::Rect::from_min_size(
+                    rect.min + vec2(point.x as f32, point.y as f32) * cell_size,
+                    vec2(cell_size, cell_size),
+                )
+            };
+            // Cells are laid out in points, the mode depends on the physical pixels drawn
+            match RenderMode::for_cell_size(cell_size * ui.ctx().pixels_per_point()) {
+                RenderMode::Tiles => {
+                    painter.rect_filled(rect, 0.0, Terrain::Land.color());
+                    let mut tiles =
+                        TileMesh::new(images.atlas, images.texture.id(), rect.min, cell_size);
+                    for (point, cell) in self.world.map().iter() {
+                        if cell.terrain != Terrain::Land {
+                            painter.rect_filled(cell_rect(point), 0.0, cell.terrain.color());
+                        }
+                        for (tile, tint) in cell.object.iter().flat_map(|object| object.tiles()) {
+                            tiles.add(point, tile, tint);
+                        }
+                    }
+                    painter.add(tiles.into_mesh());
+                }
+                RenderMode::Pixels => {
+                    let texture = render::pixel_image(self.world.map(), 1)
+                        .upload(ui.ctx(), &mut self.field_pixels)
+                        .id();
+                    painter.image(texture, rect, FULL_UV, Color32::WHITE);
+                }
+            }
+            let link = Stroke::new(cell_size / 8.0, drawing::BOT_OUTLINE_COLOR_DEF);
+            for (point, cell) in self.world.map().iter() {
+                if let Some(Object::Bot { links, .. }) = cell.object {
+                    for direction in links.iter() {
+                        painter.line_segment(
+                            render::link_segment(rect.min, cell_size, point, direction),
+                            link,
+                        );
+                    }
+                }
+            }
+            let highlight = Stroke::new(2.0, ui.visuals().selection.stroke.color);
+            if let Some(selected) = self.selected.and_then(|id| self.world.position(id)) {
+                painter.rect_stroke(cell_rect(selected), 0.0, highlight);
+            }
+
+            // Points outside the map are clamped to its border
+            let cell_at = |pos: Pos2| {
+                let cell = Point::<usize>::from(((pos - rect.min) / cell_size).to_pos2());
+                Point {
+                    x: cell.x.min(size.w - 1),
+                    y: cell.y.min(size.h - 1),
+                }
+            };
+            let pointer = response.interact_pointer_pos();
+            if !self.edit_mode {
+                if response.clicked() {
+                    let cell = pointer.map(cell_at);
+                    self.selected = cell.and_then(|cell| self.world.bot_at(cell));
+                    self.followed = self.followed.and(self.selected);
+                }
+                return;
+            }
+            if response.is_pointer_button_down_on() {
+                if let Some(pos) = pointer {
+                    let cell = cell_at(pos);
+                    if self.editor.stroke_start().is_none() {
+                        self.editor.begin_stroke(cell);
+                    }
+                    match self.editor.shape {
+                        Shape::Free if rect.contains(pos) => {
+                            self.edit_map(|map, editor| editor.paint(map, cell))
+                        }
+                        Shape::Free => self.editor.lift(),
+                        Shape::Rect => {
+                            if let Some(start) = self.editor.stroke_start() {
+                                let preview = rect_between(start, cell);
+                                painter.rect_stroke(
+                                    cell_rect(preview.top_left).union(cell_rect(Point {
+                                        x: preview.bottom_right().x - 1,
+                                        y: preview.bottom_right().y - 1,
+                                    })),
+                                    0.0,
+                                    highlight,
+                                );
+                            }
+                        }
+                    }
+                }
+            } else if let Some(start) = self.editor.stroke_start() {
+                let end = pointer.or(response.hover_pos()).map_or(start, cell_at);
+                let edit = self.edit_map(|map, editor| editor.end_stroke(map, end));
+                if let Some(edit) = edit {
+                    self.push(Action::Edit(edit));
+                    self.changed();
+                }
+            }
+        });
+        self.view = Some((output.state.offset, output.inner_rect.size()));
+    }
+
+    /// Whole field at one pixel per cell with the visible part outlined
+    fn minimap(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::right("minimap")
+            .resizable(false)
+            .show(ctx, |ui| {
+                let size = self.world.map().size();
+                let texture = render::pixel_image(self.world.map(), 1)
+                    .upload(ctx, &mut self.minimap)
+                    .id();
+                let scale = (ui.available_width() / size.w as f32).floor().max(1.0);
+                let (rect, response) = ui.allocate_exact_size(
+                    vec2(size.w as f32, size.h as f32) * scale,
+                    Sense::click_and_drag(),
                 );
+                let painter = ui.painter_at(rect);
+                painter.image(texture, rect, FULL_UV, Color32::WHITE);
+
+                let Some((offset, viewport)) = self.view else {
+                    return;
+                };
+                let cell_size = self.cell_size.px() as f32;
+                let visible = camera::visible_cells(offset, viewport, cell_size);
+                painter.rect_stroke(
+                    egui::Rect::from_min_size(
+                        rect.min + visible.min.to_vec2() * scale,
+                        visible.size() * scale,
+                    ),
+                    0.0,
+                    Stroke::new(1.0, ui.visuals().selection.stroke.color),
+                );
+                // Clicking or dragging centers the field view under the pointer
+                if response.is_pointer_button_down_on() {
+                    if let Some(pos) = response.interact_pointer_pos() {
+                        let center = (pos - rect.min) / scale;
+                        self.jump_to = Some(camera::offset_for_center(center, cell_size, viewport));
+                        self.followed = None;
+                    }
+                }
+            });
+    }
+
+    /// Records config changes made since the last call, unless the pointer is still dragging
+    fn record_config_change(&mut self, ctx: &egui::Context) {
+        let before = *self.committed_config.get_or_insert(self.sim_config);
+        if before != self.sim_config && !ctx.input(|i| i.pointer.any_down()) {
+            self.end_replay(Some("the rules changed"));
+            self.push(Action::Config {
+                before,
+                after: self.sim_config,
             });
+            self.committed_config = Some(self.sim_config);
+            self.rules_changed();
+        }
+    }
+
+    fn undo(&mut self) {
+        self.running = false;
+        self.end_steps();
+        match self.history.undo().cloned() {
+            Some(Action::Edit(edit)) => self.edit_map(|map, _| edit.undo(map)),
+            Some(Action::Config { before, .. }) => {
+                self.end_replay(Some("the rules changed"));
+                self.sim_config = before;
+                self.committed_config = Some(before);
+                self.rules_changed();
+            }
+            Some(Action::Steps { from, .. }) => {
+                if !self.go_to(from) {
+                    self.history.redo();
+                    self.notice = Some(format!("Tick {from} is too old to return to"));
+                }
+                return;
+            }
+            None => return,
+        }
+        self.changed();
+    }
+
+    fn redo(&mut self) {
+        self.running = false;
+        self.end_steps();
+        match self.history.redo().cloned() {
+            Some(Action::Edit(edit)) => self.edit_map(|map, _| edit.redo(map)),
+            Some(Action::Config { after, .. }) => {
+                self.end_replay(Some("the rules changed"));
+                self.sim_config = after;
+                self.committed_config = Some(after);
+                self.rules_changed();
+            }
+            Some(Action::Steps { to, .. }) => {
+                self.go_to(to);
+                return;
+            }
+            None => return,
+        }
+        self.changed();
+    }
+
+    fn shortcut_text(&self, ctx: &egui::Context, command: Command) -> String {
+        self.bindings
+            .get(command)
+            .map(|shortcut| ctx.format_shortcut(&shortcut))
+            .unwrap_or_default()
+    }
+
+    fn is_available(&self, command: Command) -> bool {
+        match command {
+            Command::FollowSelected => self.selected.is_some() || self.followed.is_some(),
+            Command::Undo => self.history.can_undo(),
+            Command::Redo => self.history.can_redo(),
+            Command::PlayPause
+            | Command::Step
+            | Command::ZoomIn
+            | Command::ZoomOut
+            | Command::Save
+            | Command::EditMap
+            | Command::Minimap
+            | Command::Settings
+            | Command::CommandPalette => true,
+        }
+    }
+
+    fn run(&mut self, command: Command, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        match command {
+            Command::PlayPause => {
+                self.running ^= true;
+                if !self.running {
+                    self.end_steps();
+                }
+            }
+            Command::Step => {
+                for _ in 0..self.step_ticks {
+                    self.step();
+                }
+                self.end_steps();
+            }
+            Command::FollowSelected => {
+                self.followed = match self.followed {
+                    Some(_) => None,
+                    None => self.selected,
+                }
+            }
+            Command::ZoomIn => self.cell_size = self.cell_size.larger(),
+            Command::ZoomOut => self.cell_size = self.cell_size.smaller(),
+            Command::Save => {
+                if let Some(storage) = frame.storage_mut() {
+                    self.save(storage);
+                }
+            }
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+            Command::EditMap => self.edit_mode ^= true,
+            Command::Minimap => self.show_minimap ^= true,
+            Command::Settings => self.show_settings ^= true,
+            Command::CommandPalette => {
+                self.palette = match self.palette {
+                    Some(_) => None,
+                    None => Some(String::new()),
+                };
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if let Some(command) = self.rebinding {
+            // Consumed, so the key does not also act on the UI or run its old command
+            let pressed = ctx.input_mut(|i| {
+                let shortcut = i.events.iter().find_map(|event| match *event {
+                    Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some(KeyboardShortcut::new(modifiers, key)),
+                    _ => None,
+                })?;
+                i.consume_shortcut(&shortcut);
+                Some(shortcut)
+            });
+            if let Some(shortcut) = pressed {
+                // Escape cancels
+                if shortcut.logical_key != Key::Escape {
+                    self.bindings.set(command, Some(shortcut));
+                }
+                self.rebinding = None;
+            }
+            return;
+        }
+        // Text fields have their own undo
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        if let Some(command) = ctx.input_mut(|i| self.bindings.pressed(i)) {
+            if self.is_available(command) {
+                self.run(command, ctx, frame);
+            }
+        }
+    }
+
+    fn command_palette(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let commands: Vec<_> = Command::ALL
+            .into_iter()
+            .map(|command| {
+                let shortcut = self.shortcut_text(ctx, command);
+                (command, self.is_available(command), shortcut)
+            })
+            .collect();
+        let Some(filter) = &mut self.palette else {
+            return;
+        };
+        let mut run = None;
+        let mut close = false;
+        egui::Window::new("Commands")
+            .title_bar(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, vec2(0.0, 40.0))
+            .show(ctx, |ui| {
+                ui.text_edit_singleline(filter).request_focus();
+                let filter = filter.to_lowercase();
+                let mut first = None;
+                for (command, is_available, shortcut) in commands {
+                    if !command.to_string().to_lowercase().contains(&filter) {
+                        continue;
+                    }
+                    if is_available {
+                        first = first.or(Some(command));
+                    }
+                    let button = egui::Button::new(command.to_string()).shortcut_text(shortcut);
+                    if ui.add_enabled(is_available, button).clicked() {
+                        run = Some(command);
+                    }
+                }
+                if ui.input(|i| i.key_pressed(Key::Enter)) {
+                    run = run.or(first);
+                }
+                close = ui.input(|i| i.key_pressed(Key::Escape));
+            });
+        if close || run.is_some() {
+            self.palette = None;
+        }
+        if let Some(command) = run.filter(|&command| command != Command::CommandPalette) {
+            self.run(command, ctx, frame);
+        }
     }
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+        storage.set_string(settings::KEY, settings::to_string(self));
         self.last_save = Instant::now();
+        self.last_save_tick = self.world.tick();
+        self.save_triggered = false;
+    }
+
+    /// Writes sprites shown in the central panel into a PNG in the working directory
+    fn export_image(&self, cell_size: CellSize) {
+        let images = Images::with_cell_size(cell_size);
+        let path = format!("biobots-{}.png", timestamp());
+        match export::save_png(&path, &render::sprite_image(&images, self.world.map())) {
+            Ok(()) => log::info!("Image exported to {path}"),
+            Err(e) => log::error!("Failed to export image to {path}: {e}"),
+        }
+    }
+
+    /// Records the run from the current world to reproduce it later
+    fn replay_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Replay", |ui| match &self.replay {
+            Some(replay) => {
+                ui.label(format!(
+                    "{} ticks, {} edits",
+                    replay.ticks(),
+                    replay.events.len()
+                ));
+                if ui.button("Save").clicked() {
+                    self.end_replay(None);
+                    ui.close_menu();
+                }
+            }
+            None => {
+                if ui.button("Record").clicked() {
+                    self.replay = Some(Replay::new(
+                        self.world.clone(),
+                        self.sim_config,
+                        self.initial_seed.clone(),
+                    ));
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+
+    /// Saves the recorded replay, if any. `stopped` tells why it can not go on.
+    fn end_replay(&mut self, stopped: Option<&str>) {
+        let Some(replay) = self.replay.take() else {
+            return;
+        };
+        let path = format!("biobots-{}.replay.json", timestamp());
+        let written = fs::File::create(&path)
+            .map_err(serde_json::Error::io)
+            .and_then(|file| replay.write(io::BufWriter::new(file)));
+        let result = match written {
+            Ok(()) => format!("{} ticks saved to {path}", replay.ticks()),
+            Err(e) => format!("failed to save it to {path}: {e}"),
+        };
+        self.notice = Some(match stopped {
+            Some(reason) => format!("Replay stopped since {reason}, {result}"),
+            None => format!("Replay of {result}"),
+        });
+    }
+
+    /// Records a frame of the world at the current cell size every few ticks
+    fn time_lapse_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Time-lapse", |ui| {
+            if let Some(time_lapse) = &self.time_lapse {
+                ui.label(format!("{} frames", time_lapse.frames()));
+                if ui.button("Stop").clicked() {
+                    self.time_lapse = None;
+                    ui.close_menu();
+                }
+                return;
+            }
+            ui.horizontal(|ui| {
+                ui.label("Every");
+                ui.add(egui::DragValue::new(&mut self.time_lapse_interval).range(1..=u64::MAX));
+                ui.label("ticks");
+            });
+            if ui.button("Record").clicked() {
+                let dir = format!("biobots-{}", timestamp());
+                log::info!("Recording time-lapse to {dir}");
+                self.time_lapse = Some(TimeLapse::new(dir, self.time_lapse_interval));
+                ui.close_menu();
+            }
+        });
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn apply_share_link(&mut self, link: ShareLink) {
+        self.world = link.generate_world(&self.sim_config);
+        self.initial_seed = link.initial_seed;
+        self.lock_initial_seed = true;
+        if let Some(sim_config) = link.sim_config {
+            self.sim_config = sim_config;
+        }
+        self.committed_config = None;
+        self.rng = new_seeded_rand(&self.initial_seed);
+        self.selected = None;
+        self.followed = None;
+        self.reset_history();
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn share_url(&self, frame: &eframe::Frame) -> String {
+        let link = ShareLink {
+            initial_seed: self.initial_seed.clone(),
+            map_size: self.world.map().size(),
+            sim_config: Some(self.sim_config),
+        };
+        format!(
+            "{}{}",
+            frame.info().web_info.location.url,
+            link.to_fragment()
+        )
     }
 }
 
 impl eframe::App for TemplateApp {
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.handle_shortcuts(ctx, frame);
+        if self.running {
+            self.step();
+            ctx.request_repaint();
+        }
         self.top_menu(ctx, frame);
+        if self.edit_mode {
+            self.tool_palette(ctx);
+        }
+        if self.show_minimap {
+            self.minimap(ctx);
+        }
+        self.timeline_panel(ctx);
 
         egui::CentralPanel::default().show(ctx, |ui| {
             // The central panel the region left after adding TopPanel's and SidePanel's
             ui.heading("biobots");
+            if let Some(notice) = self.notice.clone() {
+                ui.horizontal(|ui| {
+                    ui.label(notice);
+                    if ui.small_button("✖").clicked() {
+                        self.notice = None;
+                    }
+                });
+            }
 
             ui.horizontal(|ui| {
                 let images = self.images(ui).clone();
-                ui.add(Image::new(&images.organics));
-                ui.add(Image::new(&images.apple));
-                ui.add(Image::new(&images.rock));
-                ui.add(Image::new(&images.bot.body));
-                for head in &images.bot.head {
-                    ui.add(Image::new(head));
+                let cell_size = images.atlas.cell_size as f32;
+                let (rect, _) = ui.allocate_exact_size(
+                    vec2(Tile::COUNT as f32 * cell_size, cell_size),
+                    Sense::hover(),
+                );
+                let mut mesh =
+                    TileMesh::new(images.atlas, images.texture.id(), rect.min, cell_size);
+                for (x, tile) in Tile::ALL.into_iter().enumerate() {
+                    mesh.add(Point { x, y: 0 }, tile, Color32::WHITE);
                 }
+                ui.painter().add(mesh.into_mesh());
                 let bot_body = ui.add(
-                    Image::new(&images.bot.body)
+                    images
+                        .image(Tile::BotBody)
                         .tint(self.bot_color)
                         .sense(Sense::click()),
                 );
                 if ui.is_rect_visible(bot_body.rect) {
-                    Image::new(&images.bot.head[self.bot_head as usize])
+                    images
+                        .image(Tile::BotHead(self.bot_head))
                         .paint_at(ui, bot_body.rect);
                 }
                 if bot_body.clicked() {
@@ -271,18 +1300,119 @@ impl eframe::App for TemplateApp {
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
                 bottom_frame(ui);
                 ui.separator();
+                ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
+                    self.field_view(ui);
+                });
             });
         });
 
         self.settings_window(ctx);
+        self.restore_window(ctx);
+        self.command_palette(ctx, frame);
+        self.record_config_change(ctx);
+        if ctx.input(|i| i.viewport().close_requested()) {
+            self.closing = true;
+        }
     }
 
     /// Called by the framework to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        if self.auto_save.is_some() {
+        if self.auto_save_schedule.is_some() {
             self.save(storage);
         }
+        // Any save on the web may be the last one, crashes are marked on panic there
+        if self.closing || cfg!(target_arch = "wasm32") {
+            end_session(storage);
+        }
+    }
+}
+
+fn bindings_editor(ui: &mut egui::Ui, bindings: &mut Bindings, rebinding: &mut Option<Command>) {
+    egui::Grid::new("bindings").show(ui, |ui| {
+        for command in Command::ALL {
+            ui.label(command.to_string());
+            if *rebinding == Some(command) {
+                ui.label("Press a key…");
+            } else {
+                let shortcut = bindings.get(command);
+                let text = shortcut.map_or_else(
+                    || "—".to_string(),
+                    |shortcut| ui.ctx().format_shortcut(&shortcut),
+                );
+                let button = ui.button(text).on_hover_text("Click to change");
+                if button.clicked() {
+                    // Otherwise Space or Enter would press the focused button again
+                    button.surrender_focus();
+                    *rebinding = Some(command);
+                }
+            }
+            if ui.button("Clear").clicked() {
+                bindings.set(command, None);
+            }
+            ui.end_row();
+        }
+    });
+    if ui.button("Reset to defaults").clicked() {
+        bindings.reset();
+    }
+}
+
+fn auto_save_editor(ui: &mut egui::Ui, schedule: &mut Option<Schedule>) {
+    let mut enabled = schedule.is_some();
+    ui.checkbox(&mut enabled, "Enabled");
+    if enabled != schedule.is_some() {
+        *schedule = enabled.then(Schedule::default);
     }
+    let Some(schedule) = schedule else {
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.label("Every");
+        match &mut schedule.interval {
+            Interval::Seconds(seconds) => ui.add(DragValue::new(seconds).range(Interval::SECONDS)),
+            Interval::Ticks(ticks) => ui.add(DragValue::new(ticks).range(Interval::TICKS)),
+        };
+        let by_seconds = matches!(schedule.interval, Interval::Seconds(_));
+        if ui.radio(by_seconds, "seconds").clicked() && !by_seconds {
+            schedule.interval = Interval::Seconds(*Interval::SECONDS.start());
+        }
+        if ui.radio(!by_seconds, "ticks").clicked() && by_seconds {
+            schedule.interval = Interval::Ticks(*Interval::TICKS.start());
+        }
+    });
+    ui.checkbox(&mut schedule.on_extinction, "On extinction");
+    ui.horizontal(|ui| {
+        let mut by_population = schedule.population_threshold.is_some();
+        ui.checkbox(&mut by_population, "When population crosses");
+        match (by_population, &mut schedule.population_threshold) {
+            (true, Some(threshold)) => {
+                ui.add(DragValue::new(threshold).range(SimConfig::INITIAL_BOTS));
+            }
+            (true, threshold @ None) => *threshold = Some(*SimConfig::INITIAL_BOTS.end() / 100),
+            (false, threshold) => *threshold = None,
+        }
+    });
+}
+
+/// Calls `f` with the store of auto-saved world snapshots, unless there is no place for it
+#[cfg(not(target_arch = "wasm32"))]
+fn with_snapshot_store<R>(
+    _storage: &mut dyn eframe::Storage,
+    f: impl FnOnce(&mut dyn SnapshotStore) -> R,
+) -> Option<R> {
+    let dir = eframe::storage_dir("biobots")?;
+    Some(f(&mut crate::autosave::DirStore {
+        dir: dir.join("autosave"),
+    }))
+}
+
+/// Calls `f` with the store of auto-saved world snapshots
+#[cfg(target_arch = "wasm32")]
+fn with_snapshot_store<R>(
+    storage: &mut dyn eframe::Storage,
+    f: impl FnOnce(&mut dyn SnapshotStore) -> R,
+) -> Option<R> {
+    Some(f(&mut crate::autosave::AppStorage(storage)))
 }
 
 fn bottom_frame(ui: &mut egui::Ui) {
@@ -309,6 +1439,116 @@ fn bottom_frame(ui: &mut egui::Ui) {
     });
 }
 
+fn sim_config_editor(ui: &mut egui::Ui, config: &mut SimConfig, editor: &mut ConfigEditor) {
+    // Invalid draft is kept until it is fixed or reverted, a valid one follows the rules
+    let mut draft = editor
+        .draft
+        .filter(|draft| draft.validate().is_err())
+        .unwrap_or(*config);
+    ui.horizontal(|ui| {
+        ui.label("Preset:");
+        for preset in Preset::ALL {
+            if ui.button(preset.to_string()).clicked() {
+                draft = preset.config();
+            }
+        }
+    });
+    egui::Grid::new("sim_config").num_columns(2).show(ui, |ui| {
+        let energy = |ui: &mut egui::Ui, label: &str, value: &mut u32| {
+            ui.label(label);
+            ui.add(DragValue::new(value).range(SimConfig::ENERGY));
+            ui.end_row();
+        };
+        energy(ui, "Step cost:", &mut draft.energy.step);
+        energy(ui, "Rotate cost:", &mut draft.energy.rotate);
+        energy(ui, "Reproduce cost:", &mut draft.energy.reproduce);
+        energy(ui, "Transfer cost:", &mut draft.energy.transfer);
+        energy(ui, "Photosynthesis:", &mut draft.energy.photosynthesis);
+        energy(ui, "Apple energy:", &mut draft.energy.apple);
+        energy(ui, "Organics energy:", &mut draft.energy.organics);
+        energy(ui, "Max energy:", &mut draft.energy.max);
+
+        let probability = |ui: &mut egui::Ui, label: &str, value: &mut f32| {
+            ui.label(label);
+            ui.add(
+                DragValue::new(value)
+                    .range(SimConfig::PROBABILITY)
+                    .speed(0.001),
+            );
+            ui.end_row();
+        };
+        probability(ui, "Mutation rate:", &mut draft.mutation_rate);
+        probability(ui, "Apple spawn rate:", &mut draft.spawn.apple);
+        probability(ui, "Ocean:", &mut draft.terrain.ocean);
+        probability(ui, "Mud:", &mut draft.terrain.mud);
+        probability(ui, "Energy share rate:", &mut draft.colony.share_rate);
+        probability(ui, "Light absorption:", &mut draft.terrain.light_absorption);
+
+        ui.label("Lifespan:");
+        ui.add(DragValue::new(&mut draft.lifespan).range(SimConfig::LIFESPAN));
+        ui.end_row();
+        ui.label("Colonies:");
+        ui.checkbox(&mut draft.colony.link_offspring, "Link offspring");
+        ui.end_row();
+        ui.label("Kin threshold:")
+            .on_hover_text("Bots are relatives if their genomes differ in fewer genes");
+        ui.add(DragValue::new(&mut draft.kin_threshold).range(SimConfig::KIN_THRESHOLD));
+        ui.end_row();
+        ui.label("Initial bots:");
+        ui.add(DragValue::new(&mut draft.spawn.initial_bots).range(SimConfig::INITIAL_BOTS));
+        ui.end_row();
+    });
+    match draft.validate() {
+        Ok(()) => *config = draft,
+        Err(e) => {
+            ui.horizontal(|ui| {
+                ui.colored_label(ui.visuals().error_fg_color, format!("{e}, not applied"));
+                if ui.button("Revert").clicked() {
+                    draft = *config;
+                }
+            });
+        }
+    }
+    editor.draft = Some(draft);
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut editor.format, Format::Toml, Format::Toml.to_string());
+        ui.radio_value(&mut editor.format, Format::Json, Format::Json.to_string());
+        if ui.button("Export").clicked() {
+            editor.text = editor.format.export(config);
+            editor.error = None;
+            ui.ctx().copy_text(editor.text.clone());
+        }
+        if ui.button("Import").clicked() {
+            match editor.format.import(&editor.text) {
+                Ok(imported) => {
+                    *config = imported;
+                    editor.draft = None;
+                    editor.error = None;
+                }
+                Err(e) => editor.error = Some(e),
+            }
+        }
+    });
+    ui.add(
+        egui::TextEdit::multiline(&mut editor.text)
+            .code_editor()
+            .hint_text("Paste config to import"),
+    );
+    if let Some(e) = &editor.error {
+        ui.colored_label(ui.visuals().error_fg_color, e.to_string());
+    }
+}
+
+/// Seconds since the Unix epoch, for names of exported files
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 fn generate_initial_seed() -> String {
     let mut initial_seed = [0u8; 12];
     rand::thread_rng().fill(&mut initial_seed);
@@ -322,15 +1562,34 @@ fn generate_initial_seed() -> String {
     writer.into_inner()
 }
 
-fn new_seeded_rand<H, R>(h: H) -> R
-where
-    H: hash::Hash,
-    R: SeedableRng,
-    R::Seed: Fill,
-{
-    let hasher = SipHasher::from(h);
-    let mut hasher_rng = hasher.into_rng();
-    let mut seed = R::Seed::default();
-    hasher_rng.fill(&mut seed);
-    R::from_seed(seed)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Size;
+
+    #[test]
+    fn test_rewind_across_rules_change() {
+        let mut app = TemplateApp::default();
+        let config = app.sim_config;
+        app.world = World::generate(Size { w: 16, h: 12 }, &config, new_seeded_rand("seed"));
+        app.reset_history();
+        let ctx = egui::Context::default();
+        app.record_config_change(&ctx);
+        app.go_to(250);
+        app.sim_config.mutation_rate = 1.0;
+        app.record_config_change(&ctx);
+        let changed = app.world.clone();
+        app.go_to(300);
+
+        // the ticks before the change were simulated by the old rules
+        assert_eq!(app.timeline.first_tick(), Some(250));
+        assert!(!app.go_to(200));
+        assert_eq!(app.world.tick(), 300);
+        assert!(app.go_to(260));
+        let mut expected = changed;
+        for _ in 250..260 {
+            expected.step(&app.sim_config);
+        }
+        assert_eq!(app.world, expected);
+    }
 }
@@ -0,0 +1,306 @@
+use crate::config::SimConfig;
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+/// Period of auto-save.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Interval {
+    Seconds(u32),
+    /// Simulation ticks
+    Ticks(u64),
+}
+
+impl Interval {
+    pub const SECONDS: RangeInclusive<u32> = 1..=3600;
+    pub const TICKS: RangeInclusive<u64> = 1..=1_000_000;
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Interval::Seconds(seconds) => write!(f, "every {seconds} s"),
+            Interval::Ticks(ticks) => write!(f, "every {ticks} ticks"),
+        }
+    }
+}
+
+/// When auto-save happens.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[serde(default)]
+pub struct Schedule {
+    pub interval: Interval,
+    /// Also saves when the last bot dies
+    pub on_extinction: bool,
+    /// Also saves when the population crosses this number either way
+    pub population_threshold: Option<u32>,
+}
+
+impl Schedule {
+    /// Whether the interval has passed since the last save
+    pub fn is_due(&self, elapsed: Duration, ticks: u64) -> bool {
+        match self.interval {
+            Interval::Seconds(seconds) => elapsed >= Duration::from_secs(seconds.into()),
+            Interval::Ticks(interval) => ticks >= interval,
+        }
+    }
+
+    /// Whether the population change from `before` to `after` during a tick triggers a save
+    pub fn is_triggered(&self, before: u32, after: u32) -> bool {
+        let extinction = self.on_extinction && before > 0 && after == 0;
+        let crossed = self
+            .population_threshold
+            .is_some_and(|threshold| (before < threshold) != (after < threshold));
+        extinction || crossed
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            interval: Interval::Seconds(5),
+            on_extinction: false,
+            population_threshold: None,
+        }
+    }
+}
+
+/// World state written by auto-save.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct Snapshot {
+    pub initial_seed: String,
+    pub sim_config: SimConfig,
+    pub world: World,
+}
+
+/// Place for the snapshots: numbered slots of text.
+pub trait SnapshotStore {
+    fn read(&self, slot: usize) -> Option<String>;
+    fn write(&mut self, slot: usize, text: &str) -> io::Result<()>;
+}
+
+pub fn slot_name(slot: usize) -> String {
+    format!("autosave_{slot}.json")
+}
+
+/// Files in a directory, created on the first write.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct DirStore {
+    pub dir: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SnapshotStore for DirStore {
+    fn read(&self, slot: usize) -> Option<String> {
+        std::fs::read_to_string(self.dir.join(slot_name(slot))).ok()
+    }
+
+    fn write(&mut self, slot: usize, text: &str) -> io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.dir.join(slot_name(slot)), text)
+    }
+}
+
+/// App storage, i.e. local storage of the browser on the web.
+pub struct AppStorage<'a>(pub &'a mut dyn eframe::Storage);
+
+impl SnapshotStore for AppStorage<'_> {
+    fn read(&self, slot: usize) -> Option<String> {
+        self.0.get_string(&slot_name(slot))
+    }
+
+    fn write(&mut self, slot: usize, text: &str) -> io::Result<()> {
+        self.0.set_string(&slot_name(slot), text.to_string());
+        Ok(())
+    }
+}
+
+/// Storage key of the state of the last session: running, closed or crashed
+pub const SESSION_KEY: &str = "session";
+const RUNNING: &str = "running";
+const CLOSED: &str = "closed";
+const CRASHED: &str = "crashed";
+
+/// Marks this session as running. Returns whether the last one crashed,
+/// i.e. it was not closed or marked itself as crashed.
+pub fn start_session(storage: &mut dyn eframe::Storage) -> bool {
+    let last = storage.get_string(SESSION_KEY);
+    storage.set_string(SESSION_KEY, RUNNING.to_string());
+    storage.flush();
+    matches!(last.as_deref(), Some(RUNNING | CRASHED))
+}
+
+/// Marks this session as closed by the user
+pub fn end_session(storage: &mut dyn eframe::Storage) {
+    storage.set_string(SESSION_KEY, CLOSED.to_string());
+}
+
+/// Marks the session as crashed on panic. On the web the app can not save after a panic,
+/// nor tell closing the page from crashing, so it marks the session closed on every save.
+#[cfg(target_arch = "wasm32")]
+pub fn mark_crash_on_panic() {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        eframe::web::storage::local_storage_set(SESSION_KEY, CRASHED);
+        hook(info);
+    }));
+}
+
+#[derive(Deserialize, Serialize)]
+struct Slot<T> {
+    sequence: u64,
+    snapshot: T,
+}
+
+/// Writes snapshots into `slots` slots in turn, so the last `slots` snapshots are kept.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct AutoSaves {
+    slots: usize,
+    /// Sequence number of the next snapshot
+    sequence: u64,
+}
+
+impl AutoSaves {
+    /// Continues after the latest snapshot in the store
+    pub fn resume(store: &(impl SnapshotStore + ?Sized), slots: usize) -> Self {
+        Self {
+            slots: slots.max(1),
+            sequence: Self::read_latest(store, slots).map_or(0, |slot| slot.sequence + 1),
+        }
+    }
+
+    /// Latest readable snapshot of the store
+    pub fn latest(store: &(impl SnapshotStore + ?Sized), slots: usize) -> Option<Snapshot> {
+        Self::read_latest(store, slots).map(|slot| slot.snapshot)
+    }
+
+    fn read_latest(store: &(impl SnapshotStore + ?Sized), slots: usize) -> Option<Slot<Snapshot>> {
+        (0..slots)
+            .filter_map(|slot| store.read(slot))
+            .filter_map(|text| serde_json::from_str::<Slot<Snapshot>>(&text).ok())
+            .max_by_key(|slot| slot.sequence)
+    }
+
+    pub fn save(
+        &mut self,
+        store: &mut (impl SnapshotStore + ?Sized),
+        snapshot: &Snapshot,
+    ) -> io::Result<()> {
+        let slot = Slot {
+            sequence: self.sequence,
+            snapshot,
+        };
+        // SAFETY: safe to call unwrap() because Snapshot contains only plain fields
+        let text = serde_json::to_string(&slot).unwrap();
+        store.write((self.sequence % self.slots as u64) as usize, &text)?;
+        self.sequence += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Map;
+    use crate::Size;
+    use eframe::Storage;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MemoryStore(HashMap<usize, String>);
+
+    impl SnapshotStore for MemoryStore {
+        fn read(&self, slot: usize) -> Option<String> {
+            self.0.get(&slot).cloned()
+        }
+
+        fn write(&mut self, slot: usize, text: &str) -> io::Result<()> {
+            self.0.insert(slot, text.to_string());
+            Ok(())
+        }
+    }
+
+    fn snapshot(seed: usize) -> Snapshot {
+        Snapshot {
+            initial_seed: seed.to_string(),
+            sim_config: SimConfig::default(),
+            world: World::new(
+                Map::new(Size { w: 2, h: 2 }),
+                Xoshiro256PlusPlus::seed_from_u64(0),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_schedule() {
+        let mut schedule = Schedule::default();
+        assert!(!schedule.is_due(Duration::from_millis(4999), 1000));
+        assert!(schedule.is_due(Duration::from_secs(5), 0));
+        schedule.interval = Interval::Ticks(100);
+        assert!(!schedule.is_due(Duration::from_secs(60), 99));
+        assert!(schedule.is_due(Duration::ZERO, 100));
+
+        assert!(!schedule.is_triggered(1, 0));
+        schedule.on_extinction = true;
+        assert!(schedule.is_triggered(1, 0));
+        assert!(!schedule.is_triggered(0, 0));
+        schedule.population_threshold = Some(10);
+        assert!(schedule.is_triggered(9, 10));
+        assert!(schedule.is_triggered(12, 8));
+        assert!(!schedule.is_triggered(10, 12));
+    }
+
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_string(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn test_crash_detection() {
+        let mut storage = MemoryStorage::default();
+        assert!(!start_session(&mut storage));
+        end_session(&mut storage);
+        assert!(!start_session(&mut storage));
+        // Not closed
+        assert!(start_session(&mut storage));
+        storage.set_string(SESSION_KEY, CRASHED.to_string());
+        assert!(start_session(&mut storage));
+        end_session(&mut storage);
+        assert!(!start_session(&mut storage));
+    }
+
+    #[test]
+    fn test_rotation() {
+        let mut store = MemoryStore::default();
+        assert_eq!(AutoSaves::latest(&store, 3), None);
+        let mut autosaves = AutoSaves::resume(&store, 3);
+        for seed in 0..5 {
+            autosaves.save(&mut store, &snapshot(seed)).unwrap();
+        }
+        assert_eq!(store.0.len(), 3);
+        assert_eq!(AutoSaves::latest(&store, 3), Some(snapshot(4)));
+
+        // Next session continues the rotation
+        let mut autosaves = AutoSaves::resume(&store, 3);
+        autosaves.save(&mut store, &snapshot(5)).unwrap();
+        assert_eq!(AutoSaves::latest(&store, 3), Some(snapshot(5)));
+        store.0.insert(0, "broken".to_string());
+        assert_eq!(AutoSaves::latest(&store, 3), Some(snapshot(5)));
+    }
+}
@@ -1,8 +1,9 @@
 use crate::Point;
 use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
 use std::fmt;
 use std::fmt::{Display, Formatter};
-use std::ops::{Add, AddAssign};
+use std::ops::{Add, AddAssign, Sub, SubAssign};
 
 #[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
 pub enum Direction {
@@ -18,6 +19,57 @@ pub enum Direction {
 }
 
 impl Direction {
+    /// All directions clockwise, starting from North
+    pub const ALL: [Direction; 8] = [
+        Direction::N,
+        Direction::NE,
+        Direction::E,
+        Direction::SE,
+        Direction::S,
+        Direction::SW,
+        Direction::W,
+        Direction::NW,
+    ];
+    /// Directions to orthogonal (4-connected) neighbours, clockwise
+    pub const NEIGHBOURS_4: [Direction; 4] =
+        [Direction::N, Direction::E, Direction::S, Direction::W];
+    /// Directions to all (8-connected) neighbours, clockwise
+    pub const NEIGHBOURS_8: [Direction; 8] = Self::ALL;
+
+    pub fn opposite(self) -> Direction {
+        self + Direction::S
+    }
+
+    /// Rotates clockwise by `n` steps of 45 degrees
+    pub fn rotate_cw(self, n: u32) -> Direction {
+        // SAFETY: safe to call unwrap() because of mod-8 arithmetic
+        ((self as u32).wrapping_sub(n) & 0b111).try_into().unwrap()
+    }
+
+    /// Rotates counterclockwise by `n` steps of 45 degrees
+    pub fn rotate_ccw(self, n: u32) -> Direction {
+        // SAFETY: safe to call unwrap() because of mod-8 arithmetic
+        ((self as u32).wrapping_add(n) & 0b111).try_into().unwrap()
+    }
+
+    pub fn is_orthogonal(self) -> bool {
+        self as u32 & 1 == 0
+    }
+
+    /// Angle in screen coordinates (y axis points down): East is 0, positive angles go clockwise
+    pub fn angle_radians(self) -> f32 {
+        match self {
+            Direction::E => 0.0,
+            Direction::SE => FRAC_PI_4,
+            Direction::S => FRAC_PI_2,
+            Direction::SW => FRAC_PI_2 + FRAC_PI_4,
+            Direction::W => PI,
+            Direction::NW => -FRAC_PI_2 - FRAC_PI_4,
+            Direction::N => -FRAC_PI_2,
+            Direction::NE => -FRAC_PI_4,
+        }
+    }
+
     pub fn change_rotation(direction: Direction, rotate_to: Point<i32>) -> Direction {
         match rotate_to.try_into() {
             Ok(direction_shift) => direction + direction_shift,
@@ -93,6 +145,21 @@ impl AddAssign for Direction {
     }
 }
 
+impl Sub for Direction {
+    type Output = Self;
+
+    /// Rotation which turns `rhs` into `self`, i.e. `rhs + (self - rhs) == self`
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.rotate_cw(rhs as u32)
+    }
+}
+
+impl SubAssign for Direction {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
 impl Display for Direction {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
@@ -111,3 +178,49 @@ impl Display for Direction {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_clockwise() {
+        for (i, direction) in Direction::ALL.into_iter().enumerate() {
+            assert_eq!(direction.rotate_cw(1), Direction::ALL[(i + 1) % 8]);
+            assert_eq!(direction.rotate_ccw(1), Direction::ALL[(i + 7) % 8]);
+            assert_eq!(direction.rotate_cw(3), direction.rotate_ccw(5));
+            assert_eq!(direction.opposite(), Direction::ALL[(i + 4) % 8]);
+        }
+        assert_eq!(
+            Direction::NEIGHBOURS_4,
+            Direction::ALL
+                .into_iter()
+                .filter(|direction| direction.is_orthogonal())
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_sub() {
+        for a in Direction::ALL {
+            for b in Direction::ALL {
+                assert_eq!(b + (a - b), a, "{a} - {b}");
+            }
+        }
+        assert_eq!(Direction::W - Direction::N, Direction::W);
+        assert_eq!(Direction::N - Direction::E, Direction::W);
+    }
+
+    #[test]
+    fn test_angle_radians() {
+        for direction in Direction::ALL {
+            let Point { x, y } = Point::<i32>::from(direction);
+            let expected = (y as f32).atan2(x as f32);
+            assert!(
+                (direction.angle_radians() - expected).abs() < 1e-6,
+                "{direction}"
+            );
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::Point;
+use egui::{vec2, Vec2};
+use std::collections::VecDeque;
+
+/// Bots which can be followed, implemented by the world.
+pub trait Lineage {
+    type Id: Copy + Eq;
+
+    /// Cell of the bot if it is alive
+    fn position(&self, id: Self::Id) -> Option<Point<usize>>;
+    /// Offspring of the bot, living or dead, oldest first
+    fn children(&self, id: Self::Id) -> Vec<Self::Id>;
+}
+
+/// Bots searched for a living descendant before giving up
+const MAX_SEARCHED: usize = 100_000;
+
+/// Finds the followed bot, or its living descendant of the nearest generation if it died.
+/// Returns `None` if the whole lineage died out.
+pub fn follow<L: Lineage>(lineage: &L, target: L::Id) -> Option<(L::Id, Point<usize>)> {
+    let mut queue = VecDeque::from([target]);
+    let mut searched = 0;
+    while let Some(id) = queue.pop_front() {
+        if let Some(position) = lineage.position(id) {
+            return Some((id, position));
+        }
+        searched += 1;
+        if searched == MAX_SEARCHED {
+            break;
+        }
+        queue.extend(lineage.children(id));
+    }
+    None
+}
+
+/// Scroll offset which puts the center of `cell` in the middle of the viewport
+pub fn center_offset(cell: Point<usize>, cell_size: f32, viewport: Vec2) -> Vec2 {
+    let center = vec2(cell.x as f32, cell.y as f32) + vec2(0.5, 0.5);
+    offset_for_center(center, cell_size, viewport)
+}
+
+/// Scroll offset which puts `center`, measured in cells, in the middle of the viewport
+pub fn offset_for_center(center: Vec2, cell_size: f32, viewport: Vec2) -> Vec2 {
+    (center * cell_size - viewport / 2.0).max(Vec2::ZERO)
+}
+
+/// Part of the field, measured in cells, seen through the viewport scrolled by `offset`
+pub fn visible_cells(offset: Vec2, viewport: Vec2, cell_size: f32) -> egui::Rect {
+    egui::Rect::from_min_size((offset / cell_size).to_pos2(), viewport / cell_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bot `i` is alive at `(i, 0)` if `alive[i]`, its children are `children[i]`
+    struct Family {
+        alive: Vec<bool>,
+        children: Vec<Vec<usize>>,
+    }
+
+    impl Lineage for Family {
+        type Id = usize;
+
+        fn position(&self, id: usize) -> Option<Point<usize>> {
+            self.alive[id].then_some(Point { x: id, y: 0 })
+        }
+
+        fn children(&self, id: usize) -> Vec<usize> {
+            self.children[id].clone()
+        }
+    }
+
+    #[test]
+    fn test_follow() {
+        // 0 -> 1 -> 3
+        //   -> 2
+        let mut family = Family {
+            alive: vec![true, false, false, true],
+            children: vec![vec![1, 2], vec![3], vec![], vec![]],
+        };
+        assert_eq!(follow(&family, 0), Some((0, Point { x: 0, y: 0 })));
+        family.alive[0] = false;
+        assert_eq!(follow(&family, 0), Some((3, Point { x: 3, y: 0 })));
+        family.alive[2] = true;
+        assert_eq!(follow(&family, 0), Some((2, Point { x: 2, y: 0 })));
+        assert_eq!(follow(&family, 1), Some((3, Point { x: 3, y: 0 })));
+        family.alive = vec![false; 4];
+        assert_eq!(follow(&family, 0), None);
+    }
+
+    #[test]
+    fn test_center_offset() {
+        assert_eq!(
+            center_offset(Point { x: 10, y: 4 }, 8.0, vec2(40.0, 20.0)),
+            vec2(64.0, 26.0)
+        );
+        assert_eq!(
+            center_offset(Point { x: 0, y: 0 }, 8.0, vec2(40.0, 20.0)),
+            Vec2::ZERO
+        );
+    }
+
+    #[test]
+    fn test_visible_cells() {
+        let viewport = vec2(40.0, 20.0);
+        let offset = offset_for_center(vec2(10.0, 4.0), 8.0, viewport);
+        let cells = visible_cells(offset, viewport, 8.0);
+        assert_eq!(cells.center(), egui::pos2(10.0, 4.0));
+        assert_eq!(cells.size(), vec2(5.0, 2.5));
+    }
+}
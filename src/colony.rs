@@ -0,0 +1,114 @@
+use crate::bot::Direction;
+use serde::{Deserialize, Serialize};
+
+/// Directions to the neighbour cells a bot is linked with.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Links(u8);
+
+impl Links {
+    pub fn insert(&mut self, direction: Direction) {
+        self.0 |= 1 << direction as u8;
+    }
+
+    pub fn remove(&mut self, direction: Direction) {
+        self.0 &= !(1 << direction as u8);
+    }
+
+    pub fn contains(&self, direction: Direction) -> bool {
+        self.0 & (1 << direction as u8) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Linked directions, clockwise from North
+    pub fn iter(&self) -> impl Iterator<Item = Direction> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| self.contains(direction))
+    }
+}
+
+/// Equalizes `rate` part of the energy difference along each link `(a, b)` between
+/// `energy[a]` and `energy[b]`: `rate / 2` of the difference moves to the poorer bot,
+/// so `rate` of one makes them equal. Total energy is kept.
+pub fn share_energy(energy: &mut [u32], links: &[(usize, usize)], rate: f32) {
+    for &(a, b) in links {
+        let (high, low) = if energy[a] >= energy[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let amount = ((energy[high] - energy[low]) as f32 * rate / 2.0) as u32;
+        energy[high] -= amount;
+        energy[low] += amount;
+    }
+}
+
+/// Colony index of each of `count` bots, bots connected by links are in the same colony.
+/// Colonies are numbered in the order of their first bot.
+pub fn colonies(count: usize, links: &[(usize, usize)]) -> Vec<usize> {
+    let mut parent: Vec<usize> = (0..count).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for &(a, b) in links {
+        let (a, b) = (root(&mut parent, a), root(&mut parent, b));
+        parent[a.max(b)] = a.min(b);
+    }
+    let mut index = vec![usize::MAX; count];
+    let mut next = 0;
+    (0..count)
+        .map(|i| {
+            let root = root(&mut parent, i);
+            if index[root] == usize::MAX {
+                index[root] = next;
+                next += 1;
+            }
+            index[root]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links() {
+        let mut links = Links::default();
+        links.insert(Direction::W);
+        links.insert(Direction::N);
+        links.insert(Direction::W);
+        assert_eq!(
+            links.iter().collect::<Vec<_>>(),
+            [Direction::N, Direction::W]
+        );
+        links.remove(Direction::N);
+        assert!(!links.contains(Direction::N));
+        links.remove(Direction::W);
+        assert!(links.is_empty());
+    }
+
+    #[test]
+    fn test_share_energy() {
+        let mut energy = [100, 0, 50];
+        share_energy(&mut energy, &[(0, 1)], 1.0);
+        assert_eq!(energy, [50, 50, 50]);
+        share_energy(&mut energy, &[(2, 0)], 1.0);
+        assert_eq!(energy, [50, 50, 50]);
+        let mut energy = [0, 100];
+        share_energy(&mut energy, &[(0, 1)], 0.5);
+        assert_eq!(energy, [25, 75]);
+    }
+
+    #[test]
+    fn test_colonies() {
+        assert_eq!(colonies(6, &[(4, 1), (2, 5), (1, 0)]), [0, 0, 1, 2, 0, 1]);
+    }
+}
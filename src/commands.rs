@@ -0,0 +1,201 @@
+use egui::{InputState, Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Action of the app which can be bound to a key and run from the command palette.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Command {
+    PlayPause,
+    Step,
+    ZoomIn,
+    ZoomOut,
+    Save,
+    FollowSelected,
+    Undo,
+    Redo,
+    EditMap,
+    Minimap,
+    Settings,
+    CommandPalette,
+}
+
+impl Command {
+    pub const ALL: [Command; 12] = [
+        Command::PlayPause,
+        Command::Step,
+        Command::ZoomIn,
+        Command::ZoomOut,
+        Command::Save,
+        Command::FollowSelected,
+        Command::Undo,
+        Command::Redo,
+        Command::EditMap,
+        Command::Minimap,
+        Command::Settings,
+        Command::CommandPalette,
+    ];
+
+    pub fn default_shortcut(self) -> Option<KeyboardShortcut> {
+        let key = |key| Some(KeyboardShortcut::new(Modifiers::NONE, key));
+        match self {
+            Command::PlayPause => key(Key::Space),
+            Command::Step => key(Key::Period),
+            Command::ZoomIn => key(Key::Plus),
+            Command::ZoomOut => key(Key::Minus),
+            Command::Save => key(Key::S),
+            Command::FollowSelected => key(Key::F),
+            Command::Undo => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::Z)),
+            Command::Redo => Some(KeyboardShortcut::new(
+                Modifiers::COMMAND.plus(Modifiers::SHIFT),
+                Key::Z,
+            )),
+            Command::EditMap => key(Key::E),
+            Command::Minimap => key(Key::M),
+            Command::Settings => None,
+            Command::CommandPalette => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::P)),
+        }
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Command::PlayPause => "Play / pause",
+                Command::Step => "Step",
+                Command::ZoomIn => "Zoom in",
+                Command::ZoomOut => "Zoom out",
+                Command::Save => "Save",
+                Command::FollowSelected => "Follow selected bot",
+                Command::Undo => "Undo",
+                Command::Redo => "Redo",
+                Command::EditMap => "Edit map",
+                Command::Minimap => "Minimap",
+                Command::Settings => "Settings",
+                Command::CommandPalette => "Command palette",
+            }
+        )
+    }
+}
+
+/// Keyboard shortcuts of the commands. Commands missing here use their defaults,
+/// so commands added later get their shortcuts in old settings.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct Bindings {
+    shortcuts: BTreeMap<Command, Option<KeyboardShortcut>>,
+}
+
+impl Bindings {
+    pub fn get(&self, command: Command) -> Option<KeyboardShortcut> {
+        match self.shortcuts.get(&command) {
+            Some(&shortcut) => shortcut,
+            None => command.default_shortcut(),
+        }
+    }
+
+    /// Binds the shortcut, the command which had it before becomes unbound
+    pub fn set(&mut self, command: Command, shortcut: Option<KeyboardShortcut>) {
+        if shortcut.is_some() {
+            for other in Command::ALL {
+                if other != command && self.get(other) == shortcut {
+                    self.shortcuts.insert(other, None);
+                }
+            }
+        }
+        self.shortcuts.insert(command, shortcut);
+    }
+
+    pub fn reset(&mut self) {
+        self.shortcuts.clear();
+    }
+
+    /// Consumes the key press of the first bound command
+    pub fn pressed(&self, input: &mut InputState) -> Option<Command> {
+        let mut bound: Vec<_> = Command::ALL
+            .into_iter()
+            .filter_map(|command| Some((command, self.get(command)?)))
+            .collect();
+        // Ctrl+Z also matches Ctrl+Shift+Z, so shortcuts with more modifiers go first
+        bound.sort_by_key(|(_, shortcut)| {
+            let Modifiers {
+                alt,
+                ctrl,
+                shift,
+                mac_cmd,
+                command,
+            } = shortcut.modifiers;
+            Reverse(
+                [alt, ctrl, shift, mac_cmd, command]
+                    .map(u8::from)
+                    .iter()
+                    .sum::<u8>(),
+            )
+        });
+        bound
+            .into_iter()
+            .find(|(_, shortcut)| input.consume_shortcut(shortcut))
+            .map(|(command, _)| command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::Event;
+
+    fn press(key: Key, modifiers: Modifiers) -> InputState {
+        let mut input = InputState::default();
+        input.modifiers = modifiers;
+        input.events.push(Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers,
+        });
+        input
+    }
+
+    #[test]
+    fn test_pressed() {
+        let bindings = Bindings::default();
+        let mut input = press(Key::Z, Modifiers::COMMAND | Modifiers::SHIFT);
+        assert_eq!(bindings.pressed(&mut input), Some(Command::Redo));
+        assert_eq!(bindings.pressed(&mut input), None);
+        let mut input = press(Key::Z, Modifiers::COMMAND);
+        assert_eq!(bindings.pressed(&mut input), Some(Command::Undo));
+        let mut input = press(Key::Space, Modifiers::NONE);
+        assert_eq!(bindings.pressed(&mut input), Some(Command::PlayPause));
+    }
+
+    #[test]
+    fn test_set() {
+        let mut bindings = Bindings::default();
+        let space = KeyboardShortcut::new(Modifiers::NONE, Key::Space);
+        bindings.set(Command::Step, Some(space));
+        assert_eq!(bindings.get(Command::Step), Some(space));
+        assert_eq!(bindings.get(Command::PlayPause), None);
+        bindings.set(Command::Save, None);
+        assert_eq!(bindings.get(Command::Save), None);
+        bindings.reset();
+        assert_eq!(bindings, Bindings::default());
+        assert_eq!(
+            bindings.get(Command::Save),
+            Command::Save.default_shortcut()
+        );
+    }
+
+    #[test]
+    fn test_serde() {
+        let mut bindings = Bindings::default();
+        bindings.set(Command::Save, None);
+        let json = serde_json::to_string(&bindings).unwrap();
+        assert_eq!(serde_json::from_str::<Bindings>(&json).unwrap(), bindings);
+    }
+}
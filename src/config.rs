@@ -0,0 +1,390 @@
+use crate::consts::genome;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+
+/// Energy balance of a bot.
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct EnergyCosts {
+    /// Energy spent for one step
+    pub step: u32,
+    /// Energy spent for one rotation
+    pub rotate: u32,
+    /// Energy given to an offspring
+    pub reproduce: u32,
+    /// Energy gained from photosynthesis per tick in full light
+    pub photosynthesis: u32,
+    /// Energy spent for one genome transfer to a neighbour
+    pub transfer: u32,
+    /// Energy gained by eating an apple
+    pub apple: u32,
+    /// Energy gained by eating organics
+    pub organics: u32,
+    /// Maximum energy of a bot
+    pub max: u32,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct SpawnRates {
+    /// Probability of an apple to appear on an empty cell per tick
+    pub apple: f32,
+    /// Number of bots at the start of the simulation
+    pub initial_bots: u32,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct TerrainRules {
+    /// Part of the field covered by ocean
+    pub ocean: f32,
+    /// Part of the field covered by mud
+    pub mud: f32,
+    /// Part of the light absorbed by one cell of water
+    pub light_absorption: f32,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct ColonyRules {
+    /// Offspring stays linked to its parent instead of separating
+    pub link_offspring: bool,
+    /// Part of the energy difference equalized along a link per tick
+    pub share_rate: f32,
+}
+
+/// Rules of the simulation.
+#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct SimConfig {
+    pub energy: EnergyCosts,
+    /// Probability of a genome command to mutate on reproduction
+    pub mutation_rate: f32,
+    /// Maximum age of a bot in ticks
+    pub lifespan: u32,
+    /// Bots are relatives if their genomes differ in fewer genes than this
+    pub kin_threshold: u32,
+    pub spawn: SpawnRates,
+    pub terrain: TerrainRules,
+    pub colony: ColonyRules,
+}
+
+impl SimConfig {
+    pub const PROBABILITY: RangeInclusive<f32> = 0.0..=1.0;
+    pub const ENERGY: RangeInclusive<u32> = 0..=10_000;
+    pub const LIFESPAN: RangeInclusive<u32> = 1..=1_000_000;
+    pub const INITIAL_BOTS: RangeInclusive<u32> = 0..=100_000;
+    pub const KIN_THRESHOLD: RangeInclusive<u32> = 0..=genome::SIZE as u32;
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let energy = &self.energy;
+        for (value, name) in [
+            (energy.step, "energy.step"),
+            (energy.rotate, "energy.rotate"),
+            (energy.reproduce, "energy.reproduce"),
+            (energy.photosynthesis, "energy.photosynthesis"),
+            (energy.transfer, "energy.transfer"),
+            (energy.apple, "energy.apple"),
+            (energy.organics, "energy.organics"),
+            (energy.max, "energy.max"),
+        ] {
+            check_range(value, &Self::ENERGY, name)?;
+        }
+        if energy.reproduce > energy.max {
+            return Err(ConfigError::Invalid(
+                "energy.reproduce must not exceed energy.max".to_string(),
+            ));
+        }
+        for (value, name) in [
+            (self.mutation_rate, "mutation_rate"),
+            (self.spawn.apple, "spawn.apple"),
+            (self.terrain.ocean, "terrain.ocean"),
+            (self.terrain.mud, "terrain.mud"),
+            (self.terrain.light_absorption, "terrain.light_absorption"),
+            (self.colony.share_rate, "colony.share_rate"),
+        ] {
+            check_range(value, &Self::PROBABILITY, name)?;
+        }
+        check_range(self.lifespan, &Self::LIFESPAN, "lifespan")?;
+        check_range(self.kin_threshold, &Self::KIN_THRESHOLD, "kin_threshold")?;
+        check_range(
+            self.spawn.initial_bots,
+            &Self::INITIAL_BOTS,
+            "spawn.initial_bots",
+        )?;
+        if self.terrain.ocean + self.terrain.mud > 1.0 {
+            return Err(ConfigError::Invalid(
+                "terrain.ocean and terrain.mud together must not exceed 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn check_range<T>(value: T, range: &RangeInclusive<T>, name: &str) -> Result<(), ConfigError>
+where
+    T: PartialOrd + Display,
+{
+    if range.contains(&value) {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(format!(
+            "{name} must be in {}..={}, got {value}",
+            range.start(),
+            range.end()
+        )))
+    }
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Preset::Classic.config()
+    }
+}
+
+impl Default for EnergyCosts {
+    fn default() -> Self {
+        Preset::Classic.config().energy
+    }
+}
+
+impl Default for SpawnRates {
+    fn default() -> Self {
+        Preset::Classic.config().spawn
+    }
+}
+
+impl Default for TerrainRules {
+    fn default() -> Self {
+        Preset::Classic.config().terrain
+    }
+}
+
+impl Default for ColonyRules {
+    fn default() -> Self {
+        Preset::Classic.config().colony
+    }
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Preset {
+    Classic,
+    Harsh,
+    OceanWorld,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Classic, Preset::Harsh, Preset::OceanWorld];
+
+    pub fn config(self) -> SimConfig {
+        match self {
+            Preset::Classic => SimConfig {
+                energy: EnergyCosts {
+                    step: 1,
+                    rotate: 1,
+                    reproduce: 150,
+                    photosynthesis: 10,
+                    transfer: 20,
+                    apple: 100,
+                    organics: 50,
+                    max: 1000,
+                },
+                mutation_rate: 0.25,
+                lifespan: 1000,
+                kin_threshold: 4,
+                spawn: SpawnRates {
+                    apple: 0.001,
+                    initial_bots: 1000,
+                },
+                terrain: TerrainRules {
+                    ocean: 0.3,
+                    mud: 0.1,
+                    light_absorption: 0.1,
+                },
+                colony: ColonyRules {
+                    link_offspring: false,
+                    share_rate: 0.5,
+                },
+            },
+            Preset::Harsh => SimConfig {
+                energy: EnergyCosts {
+                    step: 5,
+                    rotate: 2,
+                    reproduce: 300,
+                    photosynthesis: 5,
+                    transfer: 50,
+                    apple: 50,
+                    organics: 25,
+                    max: 600,
+                },
+                mutation_rate: 0.1,
+                lifespan: 500,
+                kin_threshold: 2,
+                spawn: SpawnRates {
+                    apple: 0.0002,
+                    initial_bots: 300,
+                },
+                terrain: TerrainRules {
+                    ocean: 0.2,
+                    mud: 0.3,
+                    light_absorption: 0.2,
+                },
+                colony: ColonyRules {
+                    link_offspring: true,
+                    share_rate: 0.2,
+                },
+            },
+            Preset::OceanWorld => SimConfig {
+                energy: EnergyCosts {
+                    step: 1,
+                    rotate: 1,
+                    reproduce: 150,
+                    photosynthesis: 8,
+                    transfer: 20,
+                    apple: 100,
+                    organics: 50,
+                    max: 1000,
+                },
+                mutation_rate: 0.25,
+                lifespan: 1000,
+                kin_threshold: 4,
+                spawn: SpawnRates {
+                    apple: 0.0005,
+                    initial_bots: 1000,
+                },
+                terrain: TerrainRules {
+                    ocean: 0.9,
+                    mud: 0.05,
+                    light_absorption: 0.05,
+                },
+                colony: ColonyRules {
+                    link_offspring: false,
+                    share_rate: 0.5,
+                },
+            },
+        }
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Preset::Classic => "classic",
+                Preset::Harsh => "harsh",
+                Preset::OceanWorld => "ocean world",
+            }
+        )
+    }
+}
+
+/// Text format for import and export of [`SimConfig`].
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum Format {
+    #[default]
+    Toml,
+    Json,
+}
+
+impl Format {
+    pub fn export(self, config: &SimConfig) -> String {
+        // SAFETY: safe to call unwrap() because SimConfig contains only plain fields
+        match self {
+            Format::Toml => toml::to_string_pretty(config).unwrap(),
+            Format::Json => serde_json::to_string_pretty(config).unwrap(),
+        }
+    }
+
+    /// Parses and validates config. Missing fields are taken from the classic preset.
+    pub fn import(self, text: &str) -> Result<SimConfig, ConfigError> {
+        let config: SimConfig = match self {
+            Format::Toml => toml::from_str(text).map_err(|e| ConfigError::Parse(e.to_string()))?,
+            Format::Json => {
+                serde_json::from_str(text).map_err(|e| ConfigError::Parse(e.to_string()))?
+            }
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Format::Toml => "TOML",
+                Format::Json => "JSON",
+            }
+        )
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ConfigError {
+    Parse(String),
+    Invalid(String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Parse(e) => write!(f, "Parse error: {e}"),
+            ConfigError::Invalid(e) => write!(f, "Invalid config: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_valid() {
+        for preset in Preset::ALL {
+            assert_eq!(preset.config().validate(), Ok(()), "{preset}");
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let config = Preset::Harsh.config();
+        for format in [Format::Toml, Format::Json] {
+            assert_eq!(
+                format.import(&format.export(&config)),
+                Ok(config),
+                "{format}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_import_partial() {
+        let config = Format::Toml.import("lifespan = 42").unwrap();
+        assert_eq!(config.lifespan, 42);
+        assert_eq!(config.energy, EnergyCosts::default());
+    }
+
+    #[test]
+    fn test_import_invalid() {
+        assert!(matches!(
+            Format::Json.import(r#"{"mutation_rate": 2.0}"#),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            Format::Json.import(r#"{"terrain": {"ocean": 0.7, "mud": 0.7}}"#),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            Format::Toml.import("lifespan = "),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+}
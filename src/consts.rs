@@ -3,6 +3,35 @@ use egui::Color32;
 pub mod field {
     pub const CELL_SIZE: usize = 32;
     pub const CELL_SIZE_HALF: usize = CELL_SIZE / 2;
+    /// Size of the field in cells
+    pub const WIDTH: usize = 64;
+    pub const HEIGHT: usize = 48;
+    /// Largest side of a field generated from a share link
+    pub const MAX_SIDE: usize = 1024;
+}
+
+pub mod genome {
+    /// Number of genes in a genome
+    pub const SIZE: usize = 64;
+    /// Sensing commands executed per tick before giving up
+    pub const MAX_SENSES_PER_TICK: usize = 16;
+}
+
+pub mod history {
+    /// Undoable actions kept in the app history
+    pub const MAX_ACTIONS: usize = 100;
+}
+
+pub mod timeline {
+    /// Ticks between world snapshots kept for rewinding
+    pub const CHECKPOINT_INTERVAL: u64 = 100;
+    /// Snapshots kept for rewinding, older ones are dropped
+    pub const MAX_CHECKPOINTS: usize = 64;
+}
+
+pub mod autosave {
+    /// Auto-saved world snapshots kept, older ones are overwritten
+    pub const SLOTS: usize = 3;
 }
 
 pub mod drawing {
@@ -24,4 +53,6 @@ pub mod drawing {
     pub const MUD_COLOR: Color32 = Color32::from_rgb(140, 80, 62);
     pub const UNDERWATER_MASK_COLOR: Option<Color32> =
         Some(Color32::from_rgba_premultiplied(100, 100, 255, 80));
+    /// Cells up to this size on screen are drawn as plain pixels instead of sprites
+    pub const PIXEL_RENDER_MAX_CELL_SIZE: f32 = 8.0;
 }
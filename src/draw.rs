@@ -1,24 +1,167 @@
-use crate::Rect;
+//! Drawing primitives on [`ColorImage`].
+//!
+//! All primitives are clipped by the image bounds: pixels outside of the image are skipped.
+//! `checked_*` variants draw nothing and return [`OutOfBounds`] if the shape does not fit
+//! into the image.
+
+use crate::{Point, Rect, Size};
 use egui::{Color32, ColorImage};
-use std::ops::Range;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ops::{Range, RangeInclusive};
 
-pub fn filled_rect(image: &mut ColorImage, rect: &Rect<usize>, color: Color32) {
-    if rect.is_empty() {
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct OutOfBounds;
+
+impl Display for OutOfBounds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Shape is out of image bounds")
+    }
+}
+
+impl Error for OutOfBounds {}
+
+pub fn bounds(image: &ColorImage) -> Rect<usize> {
+    Rect {
+        top_left: Point { x: 0, y: 0 },
+        size: Size {
+            w: image.width(),
+            h: image.height(),
+        },
+    }
+}
+
+fn check(image: &ColorImage, rect: &Rect<usize>) -> Result<(), OutOfBounds> {
+    let bottom_right = rect.saturating_bottom_right();
+    if rect.is_empty() || bottom_right.x <= image.width() && bottom_right.y <= image.height() {
+        Ok(())
+    } else {
+        Err(OutOfBounds)
+    }
+}
+
+/// Checks that pixels with centers in `min..=max` are inside of the image.
+/// Empty ranges fit, NaN does not.
+fn check_extent(image: &ColorImage, min: Point<f64>, max: Point<f64>) -> Result<(), OutOfBounds> {
+    let is_empty = min.x > max.x || min.y > max.y;
+    let fits = min.x >= 0.0
+        && min.y >= 0.0
+        && max.x <= image.width() as f64 - 1.0
+        && max.y <= image.height() as f64 - 1.0;
+    if is_empty || fits {
+        Ok(())
+    } else {
+        Err(OutOfBounds)
+    }
+}
+
+/// Extent of the pixels `center - radii..=center + radii`
+fn check_radii(
+    image: &ColorImage,
+    center: Point<i32>,
+    radii: Size<i64>,
+) -> Result<(), OutOfBounds> {
+    let (x, y) = (center.x as f64, center.y as f64);
+    let (w, h) = (radii.w as f64, radii.h as f64);
+    check_extent(
+        image,
+        Point { x: x - w, y: y - h },
+        Point { x: x + w, y: y + h },
+    )
+}
+
+/// Extent of the points, `None` if there are none
+fn points_extent(points: &[Point<i32>]) -> Option<(Point<f64>, Point<f64>)> {
+    let x = points.iter().map(|point| point.x);
+    let y = points.iter().map(|point| point.y);
+    Some((
+        Point {
+            x: x.clone().min()? as f64,
+            y: y.clone().min()? as f64,
+        },
+        Point {
+            x: x.max()? as f64,
+            y: y.max()? as f64,
+        },
+    ))
+}
+
+fn bounding_rect(from: Point<usize>, to: Point<usize>) -> Rect<usize> {
+    Rect {
+        top_left: Point {
+            x: from.x.min(to.x),
+            y: from.y.min(to.y),
+        },
+        size: Size {
+            w: from.x.abs_diff(to.x).saturating_add(1),
+            h: from.y.abs_diff(to.y).saturating_add(1),
+        },
+    }
+}
+
+/// Sets pixel if it is inside of the image
+fn set(image: &mut ColorImage, x: usize, y: usize, color: Color32) {
+    if x < image.width() && y < image.height() {
+        image[(x, y)] = color;
+    }
+}
+
+/// Blends premultiplied `color` over the pixel
+pub fn blend(image: &mut ColorImage, point: Point<usize>, color: Color32) {
+    if point.x >= image.width() || point.y >= image.height() {
         return;
     }
-    let bottom_right = rect.bottom_right();
-    for y in rect.top_left.y..bottom_right.y {
-        for x in rect.top_left.x..bottom_right.x {
-            image[(x, y)] = color;
-        }
+    let dst = &mut image[(point.x, point.y)];
+    let inv_alpha = 255 - color.a() as u16;
+    let channel = |s: u8, d: u8| s.saturating_add((d as u16 * inv_alpha / 255) as u8);
+    *dst = Color32::from_rgba_premultiplied(
+        channel(color.r(), dst.r()),
+        channel(color.g(), dst.g()),
+        channel(color.b(), dst.b()),
+        channel(color.a(), dst.a()),
+    );
+}
+
+/// Blends `color` with part of it given by `coverage` in `0.0..=1.0`
+pub fn blend_coverage(image: &mut ColorImage, point: Point<usize>, color: Color32, coverage: f32) {
+    blend(image, point, color.gamma_multiply(coverage.clamp(0.0, 1.0)));
+}
+
+pub fn checked_blend(
+    image: &mut ColorImage,
+    point: Point<usize>,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check(image, &bounding_rect(point, point))?;
+    blend(image, point, color);
+    Ok(())
+}
+
+pub fn filled_rect(image: &mut ColorImage, rect: &Rect<usize>, color: Color32) {
+    let Some(rect) = rect.intersection(&bounds(image)) else {
+        return;
+    };
+    for point in rect.points() {
+        image[(point.x, point.y)] = color;
     }
 }
 
+pub fn checked_filled_rect(
+    image: &mut ColorImage,
+    rect: &Rect<usize>,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check(image, rect)?;
+    filled_rect(image, rect, color);
+    Ok(())
+}
+
 pub fn rect(image: &mut ColorImage, rect: &Rect<usize>, color: Color32) {
     if rect.is_empty() {
         return;
     }
-    let bottom_right = rect.bottom_right();
+    let bottom_right = rect.saturating_bottom_right();
     horizontal_line(
         image,
         rect.top_left.x..bottom_right.x,
@@ -47,18 +190,69 @@ pub fn rect(image: &mut ColorImage, rect: &Rect<usize>, color: Color32) {
     }
 }
 
+pub fn checked_rect(
+    image: &mut ColorImage,
+    rect: &Rect<usize>,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check(image, rect)?;
+    self::rect(image, rect, color);
+    Ok(())
+}
+
 pub fn horizontal_line(image: &mut ColorImage, x: Range<usize>, y: usize, color: Color32) {
-    for x in x {
+    if y >= image.height() {
+        return;
+    }
+    for x in x.start..x.end.min(image.width()) {
         image[(x, y)] = color;
     }
 }
 
+pub fn checked_horizontal_line(
+    image: &mut ColorImage,
+    x: Range<usize>,
+    y: usize,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check(image, &range_rect(x.clone(), y..y + 1))?;
+    horizontal_line(image, x, y, color);
+    Ok(())
+}
+
 pub fn vertical_line(image: &mut ColorImage, x: usize, y: Range<usize>, color: Color32) {
-    for y in y {
+    if x >= image.width() {
+        return;
+    }
+    for y in y.start..y.end.min(image.height()) {
         image[(x, y)] = color;
     }
 }
 
+pub fn checked_vertical_line(
+    image: &mut ColorImage,
+    x: usize,
+    y: Range<usize>,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check(image, &range_rect(x..x + 1, y.clone()))?;
+    vertical_line(image, x, y, color);
+    Ok(())
+}
+
+fn range_rect(x: Range<usize>, y: Range<usize>) -> Rect<usize> {
+    Rect {
+        top_left: Point {
+            x: x.start,
+            y: y.start,
+        },
+        size: Size {
+            w: x.len(),
+            h: y.len(),
+        },
+    }
+}
+
 pub fn diagonal_line_from_top_left(image: &mut ColorImage, rect: &Rect<usize>, color: Color32) {
     if rect.is_empty() {
         return;
@@ -67,8 +261,10 @@ pub fn diagonal_line_from_top_left(image: &mut ColorImage, rect: &Rect<usize>, c
     let dy = rect.size.h as isize - 1;
     let mut d = 2 * dy - dx;
     let mut y = rect.top_left.y;
-    for x in rect.top_left.x..rect.bottom_right().x {
-        image[(x, y)] = color;
+    // pixels right of the image are never drawn
+    let x_end = rect.saturating_bottom_right().x.min(image.width());
+    for x in rect.top_left.x..x_end {
+        set(image, x, y, color);
         if d > 0 {
             y += 1;
             d -= 2 * dx;
@@ -84,10 +280,11 @@ pub fn diagonal_line_from_bottom_left(image: &mut ColorImage, rect: &Rect<usize>
     let dx = rect.size.w as isize - 1;
     let dy = rect.size.h as isize - 1;
     let mut d = 2 * dy - dx;
-    let bottom_right = rect.bottom_right();
+    let bottom_right = rect.saturating_bottom_right();
     let mut y = bottom_right.y;
-    for x in rect.top_left.x..bottom_right.x {
-        image[(x, y - 1)] = color;
+    // pixels right of the image are never drawn
+    for x in rect.top_left.x..bottom_right.x.min(image.width()) {
+        set(image, x, y - 1, color);
         if d > 0 {
             y -= 1;
             d -= 2 * dx;
@@ -96,10 +293,580 @@ pub fn diagonal_line_from_bottom_left(image: &mut ColorImage, rect: &Rect<usize>
     }
 }
 
+pub fn checked_diagonal_line_from_top_left(
+    image: &mut ColorImage,
+    rect: &Rect<usize>,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check(image, rect)?;
+    diagonal_line_from_top_left(image, rect, color);
+    Ok(())
+}
+
+pub fn checked_diagonal_line_from_bottom_left(
+    image: &mut ColorImage,
+    rect: &Rect<usize>,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check(image, rect)?;
+    diagonal_line_from_bottom_left(image, rect, color);
+    Ok(())
+}
+
+/// Line between any two points, both ends included, clipped by the image
+pub fn line(image: &mut ColorImage, from: Point<usize>, to: Point<usize>, color: Color32) {
+    let wide = |point: Point<usize>| Point {
+        x: point.x as i128,
+        y: point.y as i128,
+    };
+    clipped_line(image, wide(from), wide(to), color);
+}
+
+pub fn checked_line(
+    image: &mut ColorImage,
+    from: Point<usize>,
+    to: Point<usize>,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check(image, &bounding_rect(from, to))?;
+    line(image, from, to, color);
+    Ok(())
+}
+
+/// Xiaolin Wu anti-aliased line, pixel centers are at integer coordinates.
+/// Non-finite ends draw nothing.
+pub fn aa_line(image: &mut ColorImage, from: Point<f32>, to: Point<f32>, color: Color32) {
+    if ![from.x, from.y, to.x, to.y]
+        .iter()
+        .all(|value| value.is_finite())
+    {
+        return;
+    }
+    // f64 holds the differences of any f32 and every pixel coordinate exactly
+    let wide = |point: Point<f32>| Point {
+        x: point.x as f64,
+        y: point.y as f64,
+    };
+    let (from, to) = (wide(from), wide(to));
+    let steep = (to.y - from.y).abs() > (to.x - from.x).abs();
+    // Work in the coordinate system where line is shallow, swap back on plotting
+    let (mut from, mut to) = if steep {
+        (
+            Point {
+                x: from.y,
+                y: from.x,
+            },
+            Point { x: to.y, y: to.x },
+        )
+    } else {
+        (from, to)
+    };
+    if from.x > to.x {
+        (from, to) = (to, from);
+    }
+    let [w, h] = image.size.map(|side| side as f64);
+    let mut plot = |x: f64, y: f64, coverage: f64| {
+        let (x, y) = if steep { (y, x) } else { (x, y) };
+        if x >= 0.0 && y >= 0.0 && x < w && y < h {
+            blend_coverage(
+                image,
+                Point {
+                    x: x as usize,
+                    y: y as usize,
+                },
+                color,
+                coverage as f32,
+            );
+        }
+    };
+
+    let dx = to.x - from.x;
+    let gradient = if dx == 0.0 { 1.0 } else { (to.y - from.y) / dx };
+
+    // Distance to the pixel center above or to the left, `f64::fract` is negative below zero
+    let fract = |value: f64| value - value.floor();
+    // Ends are covered proportionally to their horizontal overlap with the pixel
+    let mut end = |point: Point<f64>, gap: f64| {
+        let x = point.x.round();
+        let y = point.y + gradient * (x - point.x);
+        plot(x, y.floor(), (1.0 - fract(y)) * gap);
+        plot(x, y.floor() + 1.0, fract(y) * gap);
+        (x, y)
+    };
+    let (x_start, y_start) = end(from, 1.0 - fract(from.x + 0.5));
+    let (x_end, _) = end(to, fract(to.x + 0.5));
+
+    // only the columns between the ends which are inside of the image
+    let columns = if steep { h } else { w };
+    let first = (x_start + 1.0).max(0.0);
+    let last = (x_end - 1.0).min(columns - 1.0);
+    if first > last {
+        return;
+    }
+    for column in first as usize..=last as usize {
+        let x = column as f64;
+        let y = y_start + gradient * (x - x_start);
+        plot(x, y.floor(), 1.0 - fract(y));
+        plot(x, y.floor() + 1.0, fract(y));
+    }
+}
+
+/// Line of `width` pixels with round caps, pixel centers are at integer coordinates.
+/// Non-finite ends or width draw nothing.
+pub fn thick_line(
+    image: &mut ColorImage,
+    from: Point<f32>,
+    to: Point<f32>,
+    width: f32,
+    anti_aliased: bool,
+    color: Color32,
+) {
+    if image.width() == 0 || image.height() == 0 {
+        return;
+    }
+    if ![from.x, from.y, to.x, to.y, width]
+        .iter()
+        .all(|value| value.is_finite())
+    {
+        return;
+    }
+    let radius = width / 2.0;
+    let margin = radius + 1.0;
+    let clamp = |value: f32, size: usize| value.clamp(0.0, size as f32 - 1.0) as usize;
+    let (x_min, x_max) = (
+        clamp(from.x.min(to.x) - margin, image.width()),
+        clamp(from.x.max(to.x) + margin, image.width()),
+    );
+    let (y_min, y_max) = (
+        clamp(from.y.min(to.y) - margin, image.height()),
+        clamp(from.y.max(to.y) + margin, image.height()),
+    );
+    for y in y_min..=y_max {
+        for x in x_min..=x_max {
+            let distance = distance_to_segment(
+                Point {
+                    x: x as f32,
+                    y: y as f32,
+                },
+                from,
+                to,
+            );
+            let coverage = if anti_aliased {
+                radius + 0.5 - distance
+            } else if distance <= radius {
+                1.0
+            } else {
+                0.0
+            };
+            if coverage > 0.0 {
+                blend_coverage(image, Point { x, y }, color, coverage);
+            }
+        }
+    }
+}
+
+pub fn checked_aa_line(
+    image: &mut ColorImage,
+    from: Point<f32>,
+    to: Point<f32>,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check_line_extent(image, from, to, 0.0)?;
+    aa_line(image, from, to, color);
+    Ok(())
+}
+
+pub fn checked_thick_line(
+    image: &mut ColorImage,
+    from: Point<f32>,
+    to: Point<f32>,
+    width: f32,
+    anti_aliased: bool,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check_line_extent(image, from, to, width / 2.0)?;
+    thick_line(image, from, to, width, anti_aliased, color);
+    Ok(())
+}
+
+/// Extent of the segment grown by `margin` on each side
+fn check_line_extent(
+    image: &ColorImage,
+    from: Point<f32>,
+    to: Point<f32>,
+    margin: f32,
+) -> Result<(), OutOfBounds> {
+    let margin = margin.max(0.0) as f64;
+    check_extent(
+        image,
+        Point {
+            x: from.x.min(to.x) as f64 - margin,
+            y: from.y.min(to.y) as f64 - margin,
+        },
+        Point {
+            x: from.x.max(to.x) as f64 + margin,
+            y: from.y.max(to.y) as f64 + margin,
+        },
+    )
+}
+
+fn distance_to_segment(point: Point<f32>, from: Point<f32>, to: Point<f32>) -> f32 {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let length_2 = dx * dx + dy * dy;
+    let t = if length_2 == 0.0 {
+        0.0
+    } else {
+        (((point.x - from.x) * dx + (point.y - from.y) * dy) / length_2).clamp(0.0, 1.0)
+    };
+    let (px, py) = (from.x + t * dx - point.x, from.y + t * dy - point.y);
+    (px * px + py * py).sqrt()
+}
+
+/// Horizontal span `x_from..=x_to`, clipped by the image
+fn span(image: &mut ColorImage, x_from: i64, x_to: i64, y: i64, color: Color32) {
+    if y < 0 || y as usize >= image.height() {
+        return;
+    }
+    let x_from = x_from.max(0);
+    let x_to = x_to.min(image.width() as i64 - 1);
+    if x_from <= x_to {
+        horizontal_line(image, x_from as usize..x_to as usize + 1, y as usize, color);
+    }
+}
+
+/// Pixels with distance to `center` less than `radius`, clipped by the image
+pub fn filled_circle(image: &mut ColorImage, center: Point<i32>, radius: i32, color: Color32) {
+    // i64 holds the square of any i32
+    let (x, y, radius) = (center.x as i64, center.y as i64, radius as i64);
+    let radius_2 = radius * radius;
+    // only rows inside of the image
+    for row in (y - radius).max(0)..=(y + radius).min(image.height() as i64 - 1) {
+        let dy = row - y;
+        // widest dx with dx^2 + dy^2 < radius^2
+        let rest = radius_2 - dy * dy;
+        if rest <= 0 {
+            continue;
+        }
+        let dx = isqrt(rest - 1);
+        span(image, x - dx, x + dx, row, color);
+    }
+}
+
+pub fn checked_filled_circle(
+    image: &mut ColorImage,
+    center: Point<i32>,
+    radius: i32,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    if radius > 0 {
+        // farthest pixels are one less than the radius away
+        let radius = radius as i64 - 1;
+        check_radii(
+            image,
+            center,
+            Size {
+                w: radius,
+                h: radius,
+            },
+        )?;
+    }
+    filled_circle(image, center, radius, color);
+    Ok(())
+}
+
+/// Largest `n` with `n * n <= value`, `value` must not be negative
+fn isqrt(value: i64) -> i64 {
+    // float root is off by one at most for large values
+    let mut n = (value as f64).sqrt() as i64;
+    while n * n > value {
+        n -= 1;
+    }
+    while (n + 1) * (n + 1) <= value {
+        n += 1;
+    }
+    n
+}
+
+/// Circle outline, clipped by the image
+pub fn circle(image: &mut ColorImage, center: Point<i32>, radius: i32, color: Color32) {
+    ellipse(
+        image,
+        center,
+        Size {
+            w: radius,
+            h: radius,
+        },
+        color,
+    );
+}
+
+/// Ellipse outline with semi-axes `radii`: pixels of the filled ellipse with a neighbour
+/// outside of it, clipped by the image
+pub fn ellipse(image: &mut ColorImage, center: Point<i32>, radii: Size<i32>, color: Color32) {
+    if radii.w < 0 || radii.h < 0 {
+        return;
+    }
+    let (x, y) = (center.x as i64, center.y as i64);
+    let radius_y = radii.h as i64;
+    // only rows inside of the image
+    for row in (y - radius_y).max(0)..=(y + radius_y).min(image.height() as i64 - 1) {
+        let dy = row - y;
+        let dx = ellipse_half_width(radii, dy);
+        // pixels beyond the narrower of the rows around have a neighbour outside
+        let inner = ellipse_half_width(radii, dy - 1).min(ellipse_half_width(radii, dy + 1));
+        let from = (inner + 1).min(dx);
+        span(image, x + from, x + dx, row, color);
+        span(image, x - dx, x - from, row, color);
+    }
+}
+
+/// Widest `dx` of the filled ellipse in the row `dy` away from the center, -1 if the row
+/// misses it. The filled ellipse has the pixel centers inside of the ellipse with semi-axes
+/// `radii` grown by half a pixel.
+fn ellipse_half_width(radii: Size<i32>, dy: i64) -> i64 {
+    // (2dx)^2 (2b + 1)^2 + (2dy)^2 (2a + 1)^2 < (2a + 1)^2 (2b + 1)^2, u128 holds the products
+    let a_2 = (2 * radii.w as u128 + 1).pow(2);
+    let b_2 = (2 * radii.h as u128 + 1).pow(2);
+    let dy_2 = (2 * dy.unsigned_abs() as u128).pow(2);
+    if dy_2 >= b_2 {
+        return -1;
+    }
+    // largest dx with 4dx^2 b_2 <= rest - 1, which is at most a^2 + a
+    let rest = a_2 * (b_2 - dy_2);
+    isqrt(((rest - 1) / (4 * b_2)) as i64)
+}
+
+pub fn checked_circle(
+    image: &mut ColorImage,
+    center: Point<i32>,
+    radius: i32,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    checked_ellipse(
+        image,
+        center,
+        Size {
+            w: radius,
+            h: radius,
+        },
+        color,
+    )
+}
+
+pub fn checked_ellipse(
+    image: &mut ColorImage,
+    center: Point<i32>,
+    radii: Size<i32>,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    if radii.w >= 0 && radii.h >= 0 {
+        let radii = Size {
+            w: radii.w as i64,
+            h: radii.h as i64,
+        };
+        check_radii(image, center, radii)?;
+    }
+    ellipse(image, center, radii, color);
+    Ok(())
+}
+
+/// Closed outline through `points`, clipped by the image
+pub fn polygon(image: &mut ColorImage, points: &[Point<i32>], color: Color32) {
+    for (i, &from) in points.iter().enumerate() {
+        let to = points[(i + 1) % points.len()];
+        let wide = |point: Point<i32>| Point {
+            x: point.x.into(),
+            y: point.y.into(),
+        };
+        clipped_line(image, wide(from), wide(to), color);
+    }
+}
+
+pub fn checked_polygon(
+    image: &mut ColorImage,
+    points: &[Point<i32>],
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    if let Some((min, max)) = points_extent(points) {
+        check_extent(image, min, max)?;
+    }
+    polygon(image, points, color);
+    Ok(())
+}
+
+/// Pixels with centers inside of the polygon by even-odd rule, clipped by the image
+pub fn filled_polygon(image: &mut ColorImage, points: &[Point<i32>], color: Color32) {
+    let Some(y_min) = points.iter().map(|point| point.y).min() else {
+        return;
+    };
+    let y_max = points.iter().map(|point| point.y).max().unwrap_or(y_min);
+    let y_min = y_min.max(0);
+    let y_max = y_max.min(image.height() as i32 - 1);
+    let mut crossings = Vec::with_capacity(points.len());
+    for y in y_min..=y_max {
+        let scan_y = y as f32 + 0.5;
+        crossings.clear();
+        for (i, &from) in points.iter().enumerate() {
+            let to = points[(i + 1) % points.len()];
+            let (from_y, to_y) = (from.y as f32, to.y as f32);
+            if (from_y <= scan_y) != (to_y <= scan_y) {
+                let t = (scan_y - from_y) / (to_y - from_y);
+                crossings.push(from.x as f32 + t * (to.x - from.x) as f32);
+            }
+        }
+        crossings.sort_by(f32::total_cmp);
+        for pair in crossings.chunks_exact(2) {
+            // pixels with centers in pair[0]..pair[1]
+            let x_from = (pair[0] - 0.5).ceil() as i32;
+            let x_to = (pair[1] - 0.5).ceil() as i32 - 1;
+            span(image, x_from.into(), x_to.into(), y.into(), color);
+        }
+    }
+}
+
+pub fn checked_filled_polygon(
+    image: &mut ColorImage,
+    points: &[Point<i32>],
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    if let Some((min, max)) = points_extent(points) {
+        // pixels with centers inside are left and above of the farthest points
+        let max = Point {
+            x: max.x - 1.0,
+            y: max.y - 1.0,
+        };
+        check_extent(image, min, max)?;
+    }
+    filled_polygon(image, points, color);
+    Ok(())
+}
+
+/// Replaces the 4-connected area of the same color as `start` with `color`
+pub fn flood_fill(image: &mut ColorImage, start: Point<usize>, color: Color32) {
+    if start.x >= image.width() || start.y >= image.height() {
+        return;
+    }
+    let target = image[(start.x, start.y)];
+    if target == color {
+        return;
+    }
+    let mut stack = vec![start];
+    while let Some(Point { x, y }) = stack.pop() {
+        if image[(x, y)] != target {
+            continue;
+        }
+        image[(x, y)] = color;
+        if x > 0 {
+            stack.push(Point { x: x - 1, y });
+        }
+        if x + 1 < image.width() {
+            stack.push(Point { x: x + 1, y });
+        }
+        if y > 0 {
+            stack.push(Point { x, y: y - 1 });
+        }
+        if y + 1 < image.height() {
+            stack.push(Point { x, y: y + 1 });
+        }
+    }
+}
+
+pub fn checked_flood_fill(
+    image: &mut ColorImage,
+    start: Point<usize>,
+    color: Color32,
+) -> Result<(), OutOfBounds> {
+    check(image, &bounding_rect(start, start))?;
+    flood_fill(image, start, color);
+    Ok(())
+}
+
+/// Line which steps one pixel along the longer axis and takes the nearest pixel
+/// along the other one. Only the steps inside of the image are walked.
+fn clipped_line(image: &mut ColorImage, from: Point<i128>, to: Point<i128>, color: Color32) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let steps = dx.abs().max(dy.abs());
+    let x = |step: i128| from.x + div_round(step, dx, steps);
+    let y = |step: i128| from.y + div_round(step, dy, steps);
+    let Some(x_steps) = steps_between(steps, 0, image.width() as i128 - 1, x) else {
+        return;
+    };
+    let Some(y_steps) = steps_between(steps, 0, image.height() as i128 - 1, y) else {
+        return;
+    };
+    let first = *x_steps.start().max(y_steps.start());
+    let last = *x_steps.end().min(y_steps.end());
+    for step in first..=last {
+        set(image, x(step) as usize, y(step) as usize, color);
+    }
+}
+
+/// `step * delta / steps` rounded half up for `step` in `0..=steps`, `steps` of zero gives
+/// zero. u128 holds the product for the differences of any `usize`.
+fn div_round(step: i128, delta: i128, steps: i128) -> i128 {
+    if steps == 0 {
+        return 0;
+    }
+    let steps = steps as u128;
+    let product = step as u128 * delta.unsigned_abs();
+    let (quotient, remainder) = ((product / steps) as i128, product % steps);
+    // half of negative value rounds towards zero
+    if delta >= 0 {
+        quotient + (2 * remainder >= steps) as i128
+    } else {
+        -quotient - (2 * remainder > steps) as i128
+    }
+}
+
+/// Steps in `0..=steps` at which monotonic `coordinate` is in `min..=max`
+fn steps_between(
+    steps: i128,
+    min: i128,
+    max: i128,
+    coordinate: impl Fn(i128) -> i128,
+) -> Option<RangeInclusive<i128>> {
+    // rising coordinate goes below `min` first, falling one goes above `max` first
+    let is_rising = coordinate(steps) >= coordinate(0);
+    let before = |step: i128| {
+        let value = coordinate(step);
+        if is_rising {
+            value < min
+        } else {
+            value > max
+        }
+    };
+    let after = |step: i128| {
+        let value = coordinate(step);
+        if is_rising {
+            value > max
+        } else {
+            value < min
+        }
+    };
+    let first = partition_point(steps, before);
+    let end = partition_point(steps, |step| !after(step));
+    (first < end).then(|| first..=end - 1)
+}
+
+/// First step in `0..=steps` for which `is_before` is false, `steps + 1` if there is none.
+/// `is_before` must be true for a prefix of the steps only.
+fn partition_point(steps: i128, is_before: impl Fn(i128) -> bool) -> i128 {
+    let (mut low, mut high) = (0, steps + 1);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if is_before(middle) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    low
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Point, Size};
+    use proptest::prelude::*;
 
     #[test]
     fn test_diagonal_line_from_top_left() {
@@ -151,4 +918,546 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_line() {
+        let mut image = ColorImage::new([8, 8], Color32::PLACEHOLDER);
+        line(
+            &mut image,
+            Point { x: 6, y: 1 },
+            Point { x: 1, y: 6 },
+            Color32::DEBUG_COLOR,
+        );
+        for x in 0..8 {
+            for y in 0..8 {
+                assert_eq!(
+                    image[(x, y)],
+                    if x != 0 && x != 7 && x == 7 - y {
+                        Color32::DEBUG_COLOR
+                    } else {
+                        Color32::PLACEHOLDER
+                    },
+                    "at {x}, {y}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_aa_line() {
+        let mut image = ColorImage::new([8, 8], Color32::TRANSPARENT);
+        aa_line(
+            &mut image,
+            Point { x: -2.0, y: 3.5 },
+            Point { x: 10.0, y: 3.5 },
+            Color32::WHITE,
+        );
+        for x in 0..8 {
+            for y in 0..8 {
+                let expected = if y == 3 || y == 4 { 128 } else { 0 };
+                assert_eq!(image[(x, y)].a(), expected, "at {x}, {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_aa_line_above_image() {
+        let mut image = ColorImage::new([8, 8], Color32::TRANSPARENT);
+        aa_line(
+            &mut image,
+            Point { x: 0.0, y: -4.25 },
+            Point { x: 8.0, y: 3.75 },
+            Color32::WHITE,
+        );
+        // at x = 4 the line is a quarter of a pixel above the first row
+        assert_eq!(image[(4, 0)].a(), 191);
+        assert_eq!(image[(4, 1)].a(), 0);
+        assert_eq!(image[(5, 0)].a(), 64);
+        assert_eq!(image[(5, 1)].a(), 191);
+        assert_eq!(image[(3, 0)].a(), 0);
+    }
+
+    #[test]
+    fn test_thick_line() {
+        let mut image = ColorImage::new([8, 8], Color32::TRANSPARENT);
+        thick_line(
+            &mut image,
+            Point { x: 3.0, y: 4.0 },
+            Point { x: 20.0, y: 4.0 },
+            3.0,
+            false,
+            Color32::WHITE,
+        );
+        for x in 0..8 {
+            for y in 0..8 {
+                assert_eq!(
+                    image[(x, y)],
+                    // round cap reaches one pixel before the start
+                    if x >= 2 && (3..=5).contains(&y) {
+                        Color32::WHITE
+                    } else {
+                        Color32::TRANSPARENT
+                    },
+                    "at {x}, {y}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_blend() {
+        let mut image = ColorImage::new([1, 1], Color32::from_rgb(0, 0, 200));
+        blend(
+            &mut image,
+            Point { x: 0, y: 0 },
+            Color32::from_rgba_premultiplied(100, 0, 0, 128),
+        );
+        assert_eq!(
+            image[(0, 0)],
+            Color32::from_rgba_premultiplied(100, 0, 99, 255)
+        );
+    }
+
+    fn painted(image: &ColorImage) -> Vec<(usize, usize)> {
+        let mut result = vec![];
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                if image[(x, y)] == Color32::DEBUG_COLOR {
+                    result.push((x, y));
+                }
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_filled_circle() {
+        let mut image = ColorImage::new([5, 5], Color32::PLACEHOLDER);
+        filled_circle(&mut image, Point { x: 2, y: 2 }, 2, Color32::DEBUG_COLOR);
+        assert_eq!(
+            painted(&image),
+            [
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (1, 2),
+                (2, 2),
+                (3, 2),
+                (1, 3),
+                (2, 3),
+                (3, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_huge_filled_circle() {
+        let mut image = ColorImage::new([4, 3], Color32::PLACEHOLDER);
+        filled_circle(
+            &mut image,
+            Point { x: 2, y: 1 },
+            100_000,
+            Color32::DEBUG_COLOR,
+        );
+        assert_eq!(painted(&image).len(), 12);
+
+        // only the right column of the image is in the circle
+        let mut image = ColorImage::new([4, 3], Color32::PLACEHOLDER);
+        let radius = 2_000_000_000;
+        let center = Point {
+            x: 3 + radius,
+            y: 1,
+        };
+        filled_circle(&mut image, center, radius + 1, Color32::DEBUG_COLOR);
+        assert_eq!(painted(&image), [(3, 0), (3, 1), (3, 2)]);
+        assert_eq!(isqrt(i64::from(i32::MAX).pow(2)), i64::from(i32::MAX));
+        assert_eq!(isqrt(15), 3);
+    }
+
+    #[test]
+    fn test_circle() {
+        let mut image = ColorImage::new([5, 5], Color32::PLACEHOLDER);
+        circle(&mut image, Point { x: 2, y: 2 }, 2, Color32::DEBUG_COLOR);
+        let painted = painted(&image);
+        assert_eq!(painted.len(), 12);
+        for (x, y) in painted {
+            let (dx, dy) = (x as i32 - 2, y as i32 - 2);
+            assert!((3..=5).contains(&(dx * dx + dy * dy)), "at {x}, {y}");
+        }
+    }
+
+    #[test]
+    fn test_ellipse() {
+        let mut image = ColorImage::new([7, 3], Color32::PLACEHOLDER);
+        ellipse(
+            &mut image,
+            Point { x: 3, y: 1 },
+            Size { w: 3, h: 1 },
+            Color32::DEBUG_COLOR,
+        );
+        let painted = painted(&image);
+        for point in [(0, 1), (6, 1), (3, 0), (3, 2)] {
+            assert!(painted.contains(&point), "{point:?}");
+        }
+        assert!(!painted.contains(&(3, 1)));
+    }
+
+    #[test]
+    fn test_filled_polygon() {
+        let mut image = ColorImage::new([6, 6], Color32::PLACEHOLDER);
+        let square = [
+            Point { x: 1, y: 1 },
+            Point { x: 4, y: 1 },
+            Point { x: 4, y: 4 },
+            Point { x: 1, y: 4 },
+        ];
+        filled_polygon(&mut image, &square, Color32::DEBUG_COLOR);
+        let expected: Vec<_> = (1..4).flat_map(|y| (1..4).map(move |x| (x, y))).collect();
+        assert_eq!(painted(&image), expected);
+
+        let mut image = ColorImage::new([6, 6], Color32::PLACEHOLDER);
+        polygon(&mut image, &square, Color32::DEBUG_COLOR);
+        assert_eq!(painted(&image).len(), 12);
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let mut image = ColorImage::new([6, 6], Color32::PLACEHOLDER);
+        rect(
+            &mut image,
+            &Rect {
+                top_left: Point { x: 1, y: 1 },
+                size: Size { w: 4, h: 4 },
+            },
+            Color32::WHITE,
+        );
+        flood_fill(&mut image, Point { x: 2, y: 2 }, Color32::DEBUG_COLOR);
+        assert_eq!(painted(&image), [(2, 2), (3, 2), (2, 3), (3, 3)]);
+        flood_fill(&mut image, Point { x: 6, y: 0 }, Color32::DEBUG_COLOR);
+    }
+
+    #[test]
+    fn test_shapes_out_of_bounds() {
+        let mut image = ColorImage::new([4, 4], Color32::PLACEHOLDER);
+        let center = Point { x: -3, y: 10 };
+        filled_circle(&mut image, center, 5, Color32::DEBUG_COLOR);
+        circle(&mut image, center, 50, Color32::DEBUG_COLOR);
+        ellipse(
+            &mut image,
+            center,
+            Size { w: 7, h: 2 },
+            Color32::DEBUG_COLOR,
+        );
+        let triangle = [center, Point { x: 100, y: -5 }, Point { x: 2, y: 2 }];
+        polygon(&mut image, &triangle, Color32::DEBUG_COLOR);
+        filled_polygon(&mut image, &triangle, Color32::DEBUG_COLOR);
+    }
+
+    #[test]
+    fn test_checked() {
+        let mut image = ColorImage::new([4, 4], Color32::PLACEHOLDER);
+        let inside = Rect {
+            top_left: Point { x: 1, y: 1 },
+            size: Size { w: 3, h: 3 },
+        };
+        let outside = Rect {
+            top_left: Point { x: 2, y: 2 },
+            size: Size { w: 3, h: 3 },
+        };
+        assert_eq!(
+            checked_filled_rect(&mut image, &inside, Color32::DEBUG_COLOR),
+            Ok(())
+        );
+        assert_eq!(
+            checked_rect(&mut image, &outside, Color32::WHITE),
+            Err(OutOfBounds)
+        );
+        assert_eq!(
+            checked_line(
+                &mut image,
+                Point { x: 0, y: 0 },
+                Point { x: 0, y: 4 },
+                Color32::WHITE
+            ),
+            Err(OutOfBounds)
+        );
+        assert_eq!(painted(&image).len(), 9);
+    }
+
+    #[test]
+    fn test_checked_shapes() {
+        let mut image = ColorImage::new([5, 5], Color32::PLACEHOLDER);
+        let color = Color32::DEBUG_COLOR;
+        let center = Point { x: 2, y: 2 };
+        assert_eq!(
+            checked_circle(&mut image, center, 3, color),
+            Err(OutOfBounds)
+        );
+        assert_eq!(
+            checked_filled_circle(&mut image, center, 4, color),
+            Err(OutOfBounds)
+        );
+        let radii = Size { w: 3, h: 1 };
+        assert_eq!(
+            checked_ellipse(&mut image, center, radii, color),
+            Err(OutOfBounds)
+        );
+        let triangle = [
+            Point { x: 0, y: 0 },
+            Point { x: 5, y: 0 },
+            Point { x: 0, y: 4 },
+        ];
+        assert_eq!(
+            checked_polygon(&mut image, &triangle, color),
+            Err(OutOfBounds)
+        );
+        let (from, to) = (Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 4.5 });
+        assert_eq!(
+            checked_aa_line(&mut image, from, to, color),
+            Err(OutOfBounds)
+        );
+        let to = Point { x: 4.0, y: 2.0 };
+        assert_eq!(
+            checked_thick_line(&mut image, from, to, 1.0, false, color),
+            Err(OutOfBounds)
+        );
+        let start = Point { x: 5, y: 0 };
+        assert_eq!(
+            checked_flood_fill(&mut image, start, color),
+            Err(OutOfBounds)
+        );
+        assert!(painted(&image).is_empty());
+
+        assert_eq!(checked_circle(&mut image, center, 2, color), Ok(()));
+        assert_eq!(checked_filled_circle(&mut image, center, 3, color), Ok(()));
+        let radii = Size { w: 2, h: 1 };
+        assert_eq!(checked_ellipse(&mut image, center, radii, color), Ok(()));
+        assert_eq!(checked_filled_polygon(&mut image, &triangle, color), Ok(()));
+        assert_eq!(checked_aa_line(&mut image, from, to, color), Ok(()));
+        let (from, to) = (Point { x: 1.0, y: 2.0 }, Point { x: 3.0, y: 2.0 });
+        assert_eq!(
+            checked_thick_line(&mut image, from, to, 2.0, true, color),
+            Ok(())
+        );
+        assert_eq!(
+            checked_flood_fill(&mut image, Point { x: 0, y: 0 }, color),
+            Ok(())
+        );
+        assert_eq!(checked_polygon(&mut image, &[], color), Ok(()));
+    }
+
+    #[test]
+    fn test_clipped_line_far_outside() {
+        let mut image = ColorImage::new([4, 4], Color32::PLACEHOLDER);
+        let far = [Point { x: i32::MIN, y: 2 }, Point { x: i32::MAX, y: 2 }];
+        polygon(&mut image, &far, Color32::DEBUG_COLOR);
+        assert_eq!(painted(&image), [(0, 2), (1, 2), (2, 2), (3, 2)]);
+
+        let mut image = ColorImage::new([4, 4], Color32::PLACEHOLDER);
+        let outside = [Point { x: -10, y: 1 }, Point { x: 1, y: -10 }];
+        polygon(&mut image, &outside, Color32::DEBUG_COLOR);
+        assert!(painted(&image).is_empty());
+    }
+
+    #[test]
+    fn test_lines_far_outside() {
+        let mut image = ColorImage::new([4, 4], Color32::PLACEHOLDER);
+        let far = Point {
+            x: usize::MAX,
+            y: 1,
+        };
+        line(&mut image, Point { x: 0, y: 1 }, far, Color32::DEBUG_COLOR);
+        assert_eq!(painted(&image), [(0, 1), (1, 1), (2, 1), (3, 1)]);
+
+        let mut image = ColorImage::new([4, 4], Color32::TRANSPARENT);
+        let (from, to) = (Point { x: 1e8, y: 0.0 }, Point { x: 2e8, y: 0.0 });
+        aa_line(&mut image, from, to, Color32::WHITE);
+        let from = Point {
+            x: f32::NAN,
+            y: 0.0,
+        };
+        aa_line(&mut image, from, to, Color32::WHITE);
+        thick_line(&mut image, from, to, 1.0, true, Color32::WHITE);
+        assert!(image.pixels.iter().all(|pixel| pixel.a() == 0));
+
+        let (from, to) = (
+            Point {
+                x: -f32::MAX,
+                y: 1.5,
+            },
+            Point {
+                x: f32::MAX,
+                y: 1.5,
+            },
+        );
+        aa_line(&mut image, from, to, Color32::WHITE);
+        for x in 0..4 {
+            for y in 0..4 {
+                let expected = if y == 1 || y == 2 { 128 } else { 0 };
+                assert_eq!(image[(x, y)].a(), expected, "at {x}, {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_huge_shapes() {
+        let mut image = ColorImage::new([4, 4], Color32::PLACEHOLDER);
+        let radius = 1_000_000_000;
+        let center = Point {
+            x: 2,
+            y: 2 + radius,
+        };
+        circle(&mut image, center, radius, Color32::DEBUG_COLOR);
+        assert_eq!(painted(&image), [(0, 2), (1, 2), (2, 2), (3, 2)]);
+
+        let mut image = ColorImage::new([4, 4], Color32::PLACEHOLDER);
+        let radii = Size { w: i32::MAX, h: 0 };
+        ellipse(
+            &mut image,
+            Point { x: 2, y: 1 },
+            radii,
+            Color32::DEBUG_COLOR,
+        );
+        circle(
+            &mut image,
+            Point { x: 0, y: 0 },
+            i32::MAX,
+            Color32::DEBUG_COLOR,
+        );
+        assert_eq!(painted(&image), [(0, 1), (1, 1), (2, 1), (3, 1)]);
+    }
+
+    #[test]
+    fn test_clipping() {
+        let mut image = ColorImage::new([4, 4], Color32::PLACEHOLDER);
+        filled_rect(
+            &mut image,
+            &Rect {
+                top_left: Point { x: 2, y: 3 },
+                size: Size { w: 10, h: 10 },
+            },
+            Color32::DEBUG_COLOR,
+        );
+        assert_eq!(painted(&image), [(2, 3), (3, 3)]);
+    }
+
+    fn any_rect() -> impl Strategy<Value = Rect<usize>> {
+        (0..100usize, 0..100usize, 0..100usize, 0..100usize).prop_map(|(x, y, w, h)| Rect {
+            top_left: Point { x, y },
+            size: Size { w, h },
+        })
+    }
+
+    fn any_point() -> impl Strategy<Value = Point<usize>> {
+        (0..100usize, 0..100usize).prop_map(|(x, y)| Point { x, y })
+    }
+
+    fn any_far_point() -> impl Strategy<Value = Point<usize>> {
+        let coordinate = || prop_oneof![0..100usize, Just(usize::MAX), any::<usize>()];
+        (coordinate(), coordinate()).prop_map(|(x, y)| Point { x, y })
+    }
+
+    fn any_far_f32_point() -> impl Strategy<Value = Point<f32>> {
+        let coordinate = || prop_oneof![-100.0..100.0f32, any::<f32>()];
+        (coordinate(), coordinate()).prop_map(|(x, y)| Point { x, y })
+    }
+
+    proptest! {
+        #[test]
+        fn test_rect_primitives_never_panic(
+            rect in any_rect(),
+            w in 0..40usize,
+            h in 0..40usize,
+        ) {
+            let mut image = ColorImage::new([w, h], Color32::PLACEHOLDER);
+            let fits = rect.is_empty() || rect.intersection(&bounds(&image)) == Some(rect);
+            let color = Color32::DEBUG_COLOR;
+            filled_rect(&mut image, &rect, color);
+            self::rect(&mut image, &rect, color);
+            diagonal_line_from_top_left(&mut image, &rect, color);
+            diagonal_line_from_bottom_left(&mut image, &rect, color);
+            let bottom_right = rect.bottom_right();
+            horizontal_line(&mut image, rect.top_left.x..bottom_right.x, rect.top_left.y, color);
+            vertical_line(&mut image, rect.top_left.x, rect.top_left.y..bottom_right.y, color);
+            prop_assert_eq!(checked_filled_rect(&mut image, &rect, color).is_ok(), fits);
+            prop_assert_eq!(checked_rect(&mut image, &rect, color).is_ok(), fits);
+            prop_assert_eq!(
+                checked_diagonal_line_from_top_left(&mut image, &rect, color).is_ok(),
+                fits
+            );
+            prop_assert_eq!(
+                checked_diagonal_line_from_bottom_left(&mut image, &rect, color).is_ok(),
+                fits
+            );
+        }
+
+        #[test]
+        fn test_line_primitives_never_panic(
+            from in any_point(),
+            to in any_point(),
+            w in 0..40usize,
+            h in 0..40usize,
+            width in 0.0..10.0f32,
+        ) {
+            let mut image = ColorImage::new([w, h], Color32::PLACEHOLDER);
+            let color = Color32::DEBUG_COLOR;
+            line(&mut image, from, to, color);
+            blend(&mut image, from, color);
+            let from_f32 = Point { x: from.x as f32 - 50.0, y: from.y as f32 - 50.0 };
+            let to_f32 = Point { x: to.x as f32, y: to.y as f32 };
+            aa_line(&mut image, from_f32, to_f32, color);
+            thick_line(&mut image, from_f32, to_f32, width, true, color);
+            let fits = from.x.max(to.x) < w && from.y.max(to.y) < h;
+            prop_assert_eq!(checked_line(&mut image, from, to, color).is_ok(), fits);
+        }
+
+        #[test]
+        fn test_far_primitives_never_hang(
+            from in any_far_point(),
+            to in any_far_point(),
+            from_f32 in any_far_f32_point(),
+            to_f32 in any_far_f32_point(),
+            width in prop_oneof![0.0..10.0f32, any::<f32>()],
+            center in (any::<i32>(), any::<i32>()),
+            radii in (any::<i32>(), any::<i32>()),
+            w in 0..40usize,
+            h in 0..40usize,
+        ) {
+            let mut image = ColorImage::new([w, h], Color32::PLACEHOLDER);
+            let color = Color32::DEBUG_COLOR;
+            line(&mut image, from, to, color);
+            aa_line(&mut image, from_f32, to_f32, color);
+            thick_line(&mut image, from_f32, to_f32, width, true, color);
+            let center = Point { x: center.0, y: center.1 };
+            circle(&mut image, center, radii.0, color);
+            ellipse(&mut image, center, Size { w: radii.0, h: radii.1 }, color);
+            filled_circle(&mut image, center, radii.1, color);
+            let fits = from.x.max(to.x) < w && from.y.max(to.y) < h;
+            prop_assert_eq!(checked_line(&mut image, from, to, color).is_ok(), fits);
+        }
+
+        #[test]
+        fn test_clipped_line_is_part_of_whole_line(
+            from in (-50..50i32, -50..50i32),
+            to in (-50..50i32, -50..50i32),
+            w in 0..40usize,
+            h in 0..40usize,
+        ) {
+            let color = Color32::DEBUG_COLOR;
+            let mut whole = ColorImage::new([100, 100], Color32::PLACEHOLDER);
+            let shift = |(x, y): (i32, i32)| Point { x: x as i128 + 50, y: y as i128 + 50 };
+            clipped_line(&mut whole, shift(from), shift(to), color);
+            let expected: Vec<_> = painted(&whole)
+                .into_iter()
+                .filter_map(|(x, y)| Some((x.checked_sub(50)?, y.checked_sub(50)?)))
+                .filter(|&(x, y)| x < w && y < h)
+                .collect();
+            let mut clipped = ColorImage::new([w, h], Color32::PLACEHOLDER);
+            let point = |(x, y): (i32, i32)| Point { x: x.into(), y: y.into() };
+            clipped_line(&mut clipped, point(from), point(to), color);
+            let mut actual = painted(&clipped);
+            actual.sort_by_key(|&(x, y)| (y, x));
+            prop_assert_eq!(actual, expected);
+        }
+    }
 }
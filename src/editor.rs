@@ -0,0 +1,361 @@
+use crate::map::{Cell, Map, Object, Terrain};
+use crate::{Point, Rect, Size};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What painting puts into a cell.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Brush {
+    /// Changes the ground, keeps the object
+    Terrain(Terrain),
+    /// Places the object, keeps the ground
+    Object(Object),
+    /// Resets the cell to empty land
+    Eraser,
+}
+
+impl Brush {
+    pub fn apply(self, cell: Cell) -> Cell {
+        match self {
+            Brush::Terrain(terrain) => Cell { terrain, ..cell },
+            Brush::Object(object) => Cell {
+                object: Some(object),
+                ..cell
+            },
+            Brush::Eraser => Cell::default(),
+        }
+    }
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Brush::Object(Object::Rock)
+    }
+}
+
+/// How a drag over the map is painted.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum Shape {
+    /// Every cell under the pointer
+    #[default]
+    Free,
+    /// Rectangle between the start and the end of the drag
+    Rect,
+}
+
+/// Cells changed by one stroke or fill, with their contents before and after.
+#[derive(Deserialize, Serialize, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Edit {
+    changes: Vec<(Point<usize>, Cell, Cell)>,
+}
+
+impl Edit {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Cells changed by `f`, with its result
+    pub fn track<R>(map: &mut Map, f: impl FnOnce(&mut Map) -> R) -> (R, Edit) {
+        let before = map.clone();
+        let result = f(map);
+        let changes = before
+            .iter()
+            .zip(map.iter())
+            .filter(|((_, before), (_, after))| before != after)
+            .map(|((point, &before), (_, &after))| (point, before, after))
+            .collect();
+        (result, Edit { changes })
+    }
+
+    /// Edit which undoes this one when redone
+    pub fn inverse(&self) -> Edit {
+        Edit {
+            changes: self
+                .changes
+                .iter()
+                .rev()
+                .map(|&(point, before, after)| (point, after, before))
+                .collect(),
+        }
+    }
+
+    pub fn undo(&self, map: &mut Map) {
+        for &(point, before, _) in self.changes.iter().rev() {
+            map.set(point, before);
+        }
+    }
+
+    pub fn redo(&self, map: &mut Map) {
+        for &(point, _, after) in &self.changes {
+            map.set(point, after);
+        }
+    }
+}
+
+/// Paints a map with a brush, each stroke becomes an [`Edit`].
+#[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct Editor {
+    pub brush: Brush,
+    pub shape: Shape,
+    #[serde(skip)]
+    stroke: Option<Stroke>,
+}
+
+#[derive(PartialEq, Debug)]
+struct Stroke {
+    start: Point<usize>,
+    /// Last painted point, the next one is connected to it
+    last: Option<Point<usize>>,
+    edit: Edit,
+    /// Index of the change of each painted point
+    painted: HashMap<Point<usize>, usize>,
+}
+
+impl Editor {
+    pub fn begin_stroke(&mut self, start: Point<usize>) {
+        self.stroke = Some(Stroke {
+            start,
+            last: None,
+            edit: Edit::default(),
+            painted: HashMap::new(),
+        });
+    }
+
+    /// Start of the current stroke
+    pub fn stroke_start(&self) -> Option<Point<usize>> {
+        self.stroke.as_ref().map(|stroke| stroke.start)
+    }
+
+    /// Paints the cell as a part of the current stroke, starts one if needed.
+    /// Cells between the previous painted cell and this one are painted too,
+    /// so a fast drag leaves no gaps.
+    pub fn paint(&mut self, map: &mut Map, point: Point<usize>) {
+        let stroke = self.stroke.get_or_insert_with(|| Stroke {
+            start: point,
+            last: None,
+            edit: Edit::default(),
+            painted: HashMap::new(),
+        });
+        let from = stroke.last.replace(point).unwrap_or(point);
+        for point in line_between(from, point) {
+            self.paint_cell(map, point);
+        }
+    }
+
+    /// The next painted cell is not connected to the previous one
+    pub fn lift(&mut self) {
+        if let Some(stroke) = &mut self.stroke {
+            stroke.last = None;
+        }
+    }
+
+    fn paint_cell(&mut self, map: &mut Map, point: Point<usize>) {
+        let Some(stroke) = &mut self.stroke else {
+            return;
+        };
+        let Some(&before) = map.get(point) else {
+            return;
+        };
+        let after = self.brush.apply(before);
+        if after == before {
+            return;
+        }
+        map.set(point, after);
+        match stroke.painted.get(&point) {
+            Some(&i) => stroke.edit.changes[i].2 = after,
+            None => {
+                stroke.painted.insert(point, stroke.edit.changes.len());
+                stroke.edit.changes.push((point, before, after));
+            }
+        }
+    }
+
+    /// Finishes the current stroke, for [`Shape::Rect`] fills the rect from its start to `end`.
+    /// Returns `None` if nothing was changed.
+    pub fn end_stroke(&mut self, map: &mut Map, end: Point<usize>) -> Option<Edit> {
+        if self.shape == Shape::Rect {
+            if let Some(start) = self.stroke_start() {
+                for point in rect_between(start, end).points() {
+                    self.paint_cell(map, point);
+                }
+            }
+        }
+        let mut edit = self.stroke.take()?.edit;
+        // Cells painted back to what they were
+        edit.changes.retain(|(_, before, after)| before != after);
+        (!edit.is_empty()).then_some(edit)
+    }
+}
+
+/// Smallest rect containing both points
+pub fn rect_between(a: Point<usize>, b: Point<usize>) -> Rect<usize> {
+    Rect {
+        top_left: Point {
+            x: a.x.min(b.x),
+            y: a.y.min(b.y),
+        },
+        size: Size {
+            w: a.x.abs_diff(b.x) + 1,
+            h: a.y.abs_diff(b.y) + 1,
+        },
+    }
+}
+
+/// Cells of the straight line from `a` to `b`, both ends included
+pub fn line_between(a: Point<usize>, b: Point<usize>) -> impl Iterator<Item = Point<usize>> {
+    let steps = a.x.abs_diff(b.x).max(a.y.abs_diff(b.y));
+    // Coordinate of `step`, rounded to the nearest cell
+    let at = move |from: usize, to: usize, step: usize| {
+        let moved = (from.abs_diff(to) * step * 2 + steps) / (steps * 2);
+        if to > from {
+            from + moved
+        } else {
+            from - moved
+        }
+    };
+    (0..=steps).map(move |step| match steps {
+        0 => a,
+        _ => Point {
+            x: at(a.x, b.x, step),
+            y: at(a.y, b.y, step),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map() -> Map {
+        Map::new(Size { w: 4, h: 3 })
+    }
+
+    fn count(map: &Map, object: Object) -> usize {
+        map.iter()
+            .filter(|(_, cell)| cell.object == Some(object))
+            .count()
+    }
+
+    #[test]
+    fn test_stroke_undo_redo() {
+        let mut map = map();
+        let mut editor = Editor::default();
+        editor.paint(&mut map, Point { x: 0, y: 0 });
+        editor.paint(&mut map, Point { x: 1, y: 0 });
+        editor.brush = Brush::Terrain(Terrain::Mud);
+        editor.paint(&mut map, Point { x: 1, y: 0 });
+        let edit = editor.end_stroke(&mut map, Point { x: 1, y: 0 }).unwrap();
+        let painted = map.clone();
+        assert_eq!(count(&map, Object::Rock), 2);
+        assert_eq!(map.get(Point { x: 1, y: 0 }).unwrap().terrain, Terrain::Mud);
+
+        edit.undo(&mut map);
+        assert_eq!(map, self::map());
+        edit.redo(&mut map);
+        assert_eq!(map, painted);
+        assert_eq!(editor.end_stroke(&mut map, Point { x: 0, y: 0 }), None);
+    }
+
+    #[test]
+    fn test_rect_fill() {
+        let mut map = map();
+        let mut editor = Editor {
+            brush: Brush::Object(Object::Apple),
+            shape: Shape::Rect,
+            ..Default::default()
+        };
+        editor.begin_stroke(Point { x: 3, y: 2 });
+        editor.end_stroke(&mut map, Point { x: 2, y: 0 });
+        assert_eq!(count(&map, Object::Apple), 6);
+
+        editor.brush = Brush::Eraser;
+        editor.begin_stroke(Point { x: 2, y: 1 });
+        let edit = editor.end_stroke(&mut map, Point { x: 9, y: 9 }).unwrap();
+        assert_eq!(count(&map, Object::Apple), 2);
+        edit.undo(&mut map);
+        assert_eq!(count(&map, Object::Apple), 6);
+    }
+
+    #[test]
+    fn test_free_stroke_has_no_gaps() {
+        let mut map = map();
+        let mut editor = Editor::default();
+        editor.paint(&mut map, Point { x: 0, y: 0 });
+        editor.paint(&mut map, Point { x: 3, y: 2 });
+        assert_eq!(count(&map, Object::Rock), 4);
+        editor.lift();
+        editor.paint(&mut map, Point { x: 0, y: 2 });
+        assert_eq!(count(&map, Object::Rock), 5);
+        let edit = editor.end_stroke(&mut map, Point { x: 0, y: 2 }).unwrap();
+        assert_eq!(edit.changes.len(), 5);
+    }
+
+    #[test]
+    fn test_no_op_changes_are_skipped() {
+        let mut map = map();
+        let mut editor = Editor {
+            brush: Brush::Eraser,
+            ..Default::default()
+        };
+        editor.paint(&mut map, Point { x: 1, y: 1 });
+        assert_eq!(editor.end_stroke(&mut map, Point { x: 1, y: 1 }), None);
+
+        editor.brush = Brush::Object(Object::Apple);
+        editor.paint(&mut map, Point { x: 1, y: 1 });
+        editor.brush = Brush::Eraser;
+        editor.lift();
+        editor.paint(&mut map, Point { x: 1, y: 1 });
+        assert_eq!(editor.end_stroke(&mut map, Point { x: 1, y: 1 }), None);
+        assert_eq!(map, self::map());
+    }
+
+    #[test]
+    fn test_line_between() {
+        let line = |a: (usize, usize), b: (usize, usize)| {
+            let point = |(x, y)| Point { x, y };
+            line_between(point(a), point(b))
+                .map(|p| (p.x, p.y))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(line((2, 1), (2, 1)), [(2, 1)]);
+        assert_eq!(
+            line((0, 0), (4, 2)),
+            [(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+        );
+        assert_eq!(line((3, 0), (0, 1)), [(3, 0), (2, 0), (1, 1), (0, 1)]);
+        assert_eq!(line((1, 3), (1, 0)), [(1, 3), (1, 2), (1, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn test_rect_between() {
+        assert_eq!(
+            rect_between(Point { x: 3, y: 1 }, Point { x: 1, y: 2 }),
+            Rect {
+                top_left: Point { x: 1, y: 1 },
+                size: Size { w: 3, h: 2 },
+            }
+        );
+    }
+
+    #[test]
+    fn test_track_and_inverse() {
+        let mut map = map();
+        let mut editor = Editor::default();
+        let (_, first) = Edit::track(&mut map, |map| {
+            editor.paint(map, Point { x: 0, y: 0 });
+            editor.paint(map, Point { x: 2, y: 0 });
+        });
+        let (edit, second) =
+            Edit::track(&mut map, |map| editor.end_stroke(map, Point { x: 2, y: 0 }));
+        assert!(second.is_empty());
+        assert_eq!(first, edit.unwrap());
+        assert_eq!(count(&map, Object::Rock), 3);
+
+        let painted = map.clone();
+        first.inverse().redo(&mut map);
+        assert_eq!(map, self::map());
+        first.inverse().undo(&mut map);
+        assert_eq!(map, painted);
+    }
+}
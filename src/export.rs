@@ -0,0 +1,171 @@
+use crate::config::SimConfig;
+use crate::consts::field;
+use crate::images::{CellSize, Images};
+use crate::render;
+use crate::world::{new_seeded_rand, World};
+use crate::Size;
+use egui::ColorImage;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Encodes image as 8-bit RGBA PNG.
+pub fn encode_png(image: &ColorImage) -> Result<Vec<u8>, png::EncodingError> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, image.width() as u32, image.height() as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let pixels: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+        .collect();
+    writer.write_image_data(&pixels)?;
+    writer.finish()?;
+    Ok(data)
+}
+
+pub fn save_png(path: impl AsRef<Path>, image: &ColorImage) -> io::Result<()> {
+    fs::write(path, encode_png(image)?)
+}
+
+/// Writes a numbered PNG every `interval` ticks into `dir`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct TimeLapse {
+    dir: PathBuf,
+    interval: u64,
+    frame: u32,
+}
+
+impl TimeLapse {
+    /// `interval` of zero is treated as one
+    pub fn new(dir: impl Into<PathBuf>, interval: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            interval: interval.max(1),
+            frame: 0,
+        }
+    }
+
+    /// Number of written frames
+    pub fn frames(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn frame_path(&self, frame: u32) -> PathBuf {
+        self.dir.join(format!("frame_{frame:06}.png"))
+    }
+
+    /// Called on each tick, `render` is called only when a frame is due.
+    /// Returns path of the written frame.
+    pub fn record(
+        &mut self,
+        tick: u64,
+        render: impl FnOnce() -> ColorImage,
+    ) -> io::Result<Option<PathBuf>> {
+        if tick % self.interval != 0 {
+            return Ok(None);
+        }
+        fs::create_dir_all(&self.dir)?;
+        let path = self.frame_path(self.frame);
+        save_png(&path, &render())?;
+        self.frame += 1;
+        Ok(Some(path))
+    }
+}
+
+/// Steps `world` for `ticks` ticks without a window, recording frames drawn with `images`,
+/// starting with the current state.
+pub fn run_time_lapse(
+    world: &mut World,
+    config: &SimConfig,
+    ticks: u64,
+    time_lapse: &mut TimeLapse,
+    images: &Images,
+) -> io::Result<()> {
+    time_lapse.record(world.tick(), || render::sprite_image(images, world.map()))?;
+    for _ in 0..ticks {
+        world.step(config);
+        time_lapse.record(world.tick(), || render::sprite_image(images, world.map()))?;
+    }
+    Ok(())
+}
+
+/// Records a time-lapse of the world generated from `seed` by the default rules,
+/// without a window.
+pub fn record_time_lapse(
+    seed: &str,
+    ticks: u64,
+    time_lapse: &mut TimeLapse,
+    cell_size: CellSize,
+) -> io::Result<()> {
+    let config = SimConfig::default();
+    let size = Size {
+        w: field::WIDTH,
+        h: field::HEIGHT,
+    };
+    let mut world = World::generate(size, &config, new_seeded_rand(seed));
+    let images = Images::with_cell_size(cell_size);
+    run_time_lapse(&mut world, &config, ticks, time_lapse, &images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::CellSize;
+    use egui::Color32;
+
+    #[test]
+    fn test_encode_png() {
+        let mut image = ColorImage::new([3, 2], Color32::TRANSPARENT);
+        image[(1, 1)] = Color32::from_rgb(1, 2, 3);
+        let data = encode_png(&image).unwrap();
+
+        let decoder = png::Decoder::new(data.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(&buf[16..20], &[1, 2, 3, 255]);
+        assert_eq!(&buf[0..4], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_time_lapse() {
+        let dir = std::env::temp_dir().join(format!("biobots-time-lapse-{}", std::process::id()));
+        let mut time_lapse = TimeLapse::new(&dir, 10);
+        let mut written = vec![];
+        for tick in 0..25 {
+            if let Some(path) = time_lapse
+                .record(tick, || ColorImage::new([1, 1], Color32::WHITE))
+                .unwrap()
+            {
+                written.push(path);
+            }
+        }
+        assert_eq!(written, [0, 1, 2].map(|frame| time_lapse.frame_path(frame)));
+        assert!(written.iter().all(|path| path.is_file()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_time_lapse() {
+        let dir = std::env::temp_dir().join(format!("biobots-headless-{}", std::process::id()));
+        let mut time_lapse = TimeLapse::new(&dir, 2);
+        let config = SimConfig::default();
+        let mut world = World::default();
+        let images = Images::with_cell_size(CellSize::Eight);
+        run_time_lapse(&mut world, &config, 5, &mut time_lapse, &images).unwrap();
+        assert_eq!(world.tick(), 5);
+        assert_eq!(time_lapse.frames(), 3);
+        let file = fs::File::open(time_lapse.frame_path(2)).unwrap();
+        let info = png::Decoder::new(file).read_info().unwrap().info().clone();
+        let size = world.map().size();
+        assert_eq!(
+            (info.width as usize, info.height as usize),
+            (size.w * 8, size.h * 8)
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,510 @@
+use crate::bot::Direction;
+use crate::consts::genome;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// What a bot sees in a neighbour cell.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Seen {
+    Empty = 0,
+    Rock = 1,
+    Apple = 2,
+    Organics = 3,
+    /// Bot of the kin, see [`is_kin`]
+    Relative = 4,
+    /// Bot which is not of the kin
+    Foreign = 5,
+    Water = 6,
+}
+
+impl Seen {
+    pub const COUNT: usize = 7;
+}
+
+/// Perception of a bot, implemented by the world.
+///
+/// Levels are parts of the maximum in `0.0..=1.0`.
+pub trait Senses {
+    fn look(&self, direction: Direction) -> Seen;
+    /// Genome of the bot in the neighbour cell
+    fn genome_at(&self, direction: Direction) -> Option<&Genome>;
+    /// See [`is_kin`]
+    fn kin_threshold(&self) -> usize;
+    /// Part of the maximum energy
+    fn energy(&self) -> f32;
+    /// Part of the lifespan
+    fn age(&self) -> f32;
+    fn depth(&self) -> f32;
+    fn light(&self) -> f32;
+    fn minerals(&self) -> f32;
+}
+
+/// Sensing commands, all other gene values are left to the caller.
+///
+/// Gene after the command is its parameter, genes after the parameter are
+/// relative jumps taken depending on the outcome.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Command {
+    /// Looks in the direction relative to the facing one, given by the parameter,
+    /// jumps by one of [`Seen::COUNT`] genes depending on what is seen
+    Look = 32,
+    /// Jumps by the first gene if own energy is at least the parameter part of the maximum,
+    /// by the second one otherwise
+    Energy = 33,
+    /// Same as [`Command::Energy`] for the age
+    Age = 34,
+    /// Same as [`Command::Energy`] for the depth
+    Depth = 35,
+    /// Same as [`Command::Energy`] for the local light
+    Light = 36,
+    /// Same as [`Command::Energy`] for the local minerals
+    Minerals = 37,
+    /// Looks in the direction relative to the facing one, given by the parameter,
+    /// jumps by the first gene if there is a relative bot, by the second one otherwise
+    IsRelative = 38,
+}
+
+impl TryFrom<u8> for Command {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            32 => Command::Look,
+            33 => Command::Energy,
+            34 => Command::Age,
+            35 => Command::Depth,
+            36 => Command::Light,
+            37 => Command::Minerals,
+            38 => Command::IsRelative,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// Genome transfer commands, executed by the world on the bot in the facing cell.
+///
+/// Parameter is the length of the segment starting at the command.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Transfer {
+    /// Overwrites the segment of the neighbour's genome with own one
+    Infect = 39,
+    /// Swaps the segment with the neighbour
+    Conjugate = 40,
+}
+
+impl TryFrom<u8> for Transfer {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            39 => Transfer::Infect,
+            40 => Transfer::Conjugate,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// Genome transfers which happened during a tick.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct TransferCounter {
+    pub infections: u32,
+    pub conjugations: u32,
+}
+
+impl TransferCounter {
+    pub fn record(&mut self, transfer: Transfer) {
+        match transfer {
+            Transfer::Infect => self.infections += 1,
+            Transfer::Conjugate => self.conjugations += 1,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.infections + self.conjugations
+    }
+}
+
+/// Number of genes in which genomes differ
+pub fn distance(a: &Genome, b: &Genome) -> usize {
+    a.genes.iter().zip(&b.genes).filter(|(a, b)| a != b).count()
+}
+
+/// Genomes are of the kin if they differ in fewer than `threshold` genes
+pub fn is_kin(a: &Genome, b: &Genome, threshold: usize) -> bool {
+    distance(a, b) < threshold
+}
+
+#[derive(Deserialize, Serialize, Clone, Eq, PartialEq, Hash, Debug)]
+#[serde(try_from = "RawGenome", into = "RawGenome")]
+pub struct Genome {
+    genes: [u8; genome::SIZE],
+    /// Always less than [`genome::SIZE`]
+    pointer: usize,
+}
+
+/// Serialized genome, checked to have [`genome::SIZE`] genes and the pointer among them.
+#[derive(Deserialize, Serialize)]
+struct RawGenome {
+    genes: Vec<u8>,
+    pointer: usize,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum InvalidGenome {
+    Size(usize),
+    Pointer(usize),
+}
+
+impl Display for InvalidGenome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidGenome::Size(size) => {
+                write!(f, "Genome has {size} genes instead of {}", genome::SIZE)
+            }
+            InvalidGenome::Pointer(pointer) => {
+                write!(
+                    f,
+                    "Genome pointer {pointer} is out of {} genes",
+                    genome::SIZE
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidGenome {}
+
+impl TryFrom<RawGenome> for Genome {
+    type Error = InvalidGenome;
+
+    fn try_from(RawGenome { genes, pointer }: RawGenome) -> Result<Self, Self::Error> {
+        let size = genes.len();
+        let genes = genes.try_into().map_err(|_| InvalidGenome::Size(size))?;
+        if pointer >= genome::SIZE {
+            return Err(InvalidGenome::Pointer(pointer));
+        }
+        Ok(Self { genes, pointer })
+    }
+}
+
+impl From<Genome> for RawGenome {
+    fn from(Genome { genes, pointer }: Genome) -> Self {
+        Self {
+            genes: genes.to_vec(),
+            pointer,
+        }
+    }
+}
+
+impl From<[u8; genome::SIZE]> for Genome {
+    fn from(genes: [u8; genome::SIZE]) -> Self {
+        Self { genes, pointer: 0 }
+    }
+}
+
+impl Genome {
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        Self {
+            genes: std::array::from_fn(|_| rng.gen_range(0..genome::SIZE as u8)),
+            pointer: 0,
+        }
+    }
+
+    /// Genome of an offspring, executed from the first gene.
+    /// With probability `mutation_rate` one random gene gets a random value.
+    pub fn offspring<R: Rng>(&self, rng: &mut R, mutation_rate: f32) -> Self {
+        let mut genes = self.genes;
+        if rng.gen::<f32>() < mutation_rate {
+            genes[rng.gen_range(0..genome::SIZE)] = rng.gen_range(0..genome::SIZE as u8);
+        }
+        Self { genes, pointer: 0 }
+    }
+
+    pub fn genes(&self) -> &[u8] {
+        &self.genes
+    }
+
+    pub fn pointer(&self) -> usize {
+        self.pointer
+    }
+
+    /// Gene `shift` positions after the pointer
+    pub fn gene(&self, shift: usize) -> u8 {
+        self.genes[(self.pointer + shift) % genome::SIZE]
+    }
+
+    fn jump(&mut self, shift: usize) {
+        self.pointer = (self.pointer + self.gene(shift) as usize) % genome::SIZE;
+    }
+
+    /// Moves the pointer forward by `shift` genes
+    pub fn skip(&mut self, shift: usize) {
+        self.pointer = (self.pointer + shift) % genome::SIZE;
+    }
+
+    /// Executes sensing commands starting from the current gene.
+    /// Returns the first gene which is not a sensing command, the pointer is left on it.
+    /// Returns `None` if the limit of sensing commands per tick is exceeded.
+    pub fn sense(&mut self, facing: Direction, senses: &impl Senses) -> Option<u8> {
+        for _ in 0..genome::MAX_SENSES_PER_TICK {
+            let gene = self.gene(0);
+            let Ok(command) = Command::try_from(gene) else {
+                return Some(gene);
+            };
+            let level = |value: f32| value >= self.gene(1) as f32 / genome::SIZE as f32;
+            let direction = facing.rotate_cw((self.gene(1) & 0b111) as u32);
+            let is_true = match command {
+                Command::Look => {
+                    let seen = senses.look(direction);
+                    self.jump(2 + seen as usize);
+                    continue;
+                }
+                Command::IsRelative => senses
+                    .genome_at(direction)
+                    .is_some_and(|other| is_kin(self, other, senses.kin_threshold())),
+                Command::Energy => level(senses.energy()),
+                Command::Age => level(senses.age()),
+                Command::Depth => level(senses.depth()),
+                Command::Light => level(senses.light()),
+                Command::Minerals => level(senses.minerals()),
+            };
+            self.jump(if is_true { 2 } else { 3 });
+        }
+        None
+    }
+
+    /// Executes the transfer command at the pointer with the `other` genome
+    /// and moves the pointer past its parameter.
+    /// Returns `None` and does nothing if the current gene is not a transfer command.
+    pub fn transfer(&mut self, other: &mut Genome) -> Option<Transfer> {
+        let transfer = Transfer::try_from(self.gene(0)).ok()?;
+        let len = self.gene(1) as usize % genome::SIZE;
+        for shift in 0..len {
+            let i = (self.pointer + shift) % genome::SIZE;
+            match transfer {
+                Transfer::Infect => other.genes[i] = self.genes[i],
+                Transfer::Conjugate => std::mem::swap(&mut self.genes[i], &mut other.genes[i]),
+            }
+        }
+        self.pointer = (self.pointer + 2) % genome::SIZE;
+        Some(transfer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed;
+
+    impl Senses for Fixed {
+        fn look(&self, direction: Direction) -> Seen {
+            match direction {
+                Direction::E => Seen::Apple,
+                _ => Seen::Rock,
+            }
+        }
+
+        fn genome_at(&self, _: Direction) -> Option<&Genome> {
+            None
+        }
+
+        fn kin_threshold(&self) -> usize {
+            0
+        }
+
+        fn energy(&self) -> f32 {
+            0.5
+        }
+
+        fn age(&self) -> f32 {
+            0.0
+        }
+
+        fn depth(&self) -> f32 {
+            0.0
+        }
+
+        fn light(&self) -> f32 {
+            1.0
+        }
+
+        fn minerals(&self) -> f32 {
+            0.0
+        }
+    }
+
+    fn genome(prefix: &[u8]) -> Genome {
+        let mut genes = [0; genome::SIZE];
+        genes[..prefix.len()].copy_from_slice(prefix);
+        Genome::from(genes)
+    }
+
+    #[test]
+    fn test_look() {
+        // facing North, look 2 steps clockwise: East, apple is the third outcome
+        let mut genome = genome(&[Command::Look as u8, 2, 10, 11, 12, 13, 14, 15, 16]);
+        genome.genes[12] = 1;
+        assert_eq!(genome.sense(Direction::N, &Fixed), Some(1));
+        assert_eq!(genome.pointer(), 12);
+
+        genome.pointer = 0;
+        genome.genes[1] = 0;
+        genome.genes[11] = 2;
+        assert_eq!(genome.sense(Direction::N, &Fixed), Some(2));
+        assert_eq!(genome.pointer(), 11);
+    }
+
+    #[test]
+    fn test_levels() {
+        let half = genome::SIZE as u8 / 2;
+        let mut genome = genome(&[Command::Energy as u8, half, 4, 5, 1, 2]);
+        assert_eq!(genome.sense(Direction::N, &Fixed), Some(1));
+        genome.pointer = 0;
+        genome.genes[1] = half + 1;
+        assert_eq!(genome.sense(Direction::N, &Fixed), Some(2));
+    }
+
+    #[test]
+    fn test_sense_limit() {
+        // jumps to itself forever
+        let mut genome = genome(&[Command::Minerals as u8, 1, 0, 0]);
+        assert_eq!(genome.sense(Direction::N, &Fixed), None);
+    }
+
+    #[test]
+    fn test_distance() {
+        let a = genome(&[1, 2, 3]);
+        let b = genome(&[1, 5, 6]);
+        assert_eq!(distance(&a, &b), 2);
+        assert_eq!(distance(&a, &a), 0);
+        assert!(is_kin(&a, &b, 3));
+        assert!(!is_kin(&a, &b, 2));
+    }
+
+    #[test]
+    fn test_is_relative() {
+        struct Neighbour(Genome);
+
+        impl Senses for Neighbour {
+            fn look(&self, _: Direction) -> Seen {
+                Seen::Relative
+            }
+
+            fn genome_at(&self, direction: Direction) -> Option<&Genome> {
+                (direction == Direction::S).then_some(&self.0)
+            }
+
+            fn kin_threshold(&self) -> usize {
+                2
+            }
+
+            fn energy(&self) -> f32 {
+                0.0
+            }
+
+            fn age(&self) -> f32 {
+                0.0
+            }
+
+            fn depth(&self) -> f32 {
+                0.0
+            }
+
+            fn light(&self) -> f32 {
+                0.0
+            }
+
+            fn minerals(&self) -> f32 {
+                0.0
+            }
+        }
+
+        // facing East, look 2 steps clockwise: South
+        let prefix = [Command::IsRelative as u8, 2, 4, 5, 1, 2];
+        let mut relative = genome(&prefix);
+        relative.genes[10] = 7;
+        let senses = Neighbour(relative);
+        let mut genome = genome(&prefix);
+        assert_eq!(genome.sense(Direction::E, &senses), Some(1));
+
+        genome.pointer = 0;
+        genome.genes[11] = 8;
+        assert_eq!(genome.sense(Direction::E, &senses), Some(2));
+
+        genome.pointer = 0;
+        assert_eq!(genome.sense(Direction::N, &senses), Some(2));
+    }
+
+    #[test]
+    fn test_transfer() {
+        let mut virus = genome(&[Transfer::Infect as u8, 3, 9, 9]);
+        let mut host = genome(&[1, 2, 3, 4]);
+        assert_eq!(virus.transfer(&mut host), Some(Transfer::Infect));
+        assert_eq!(host.genes()[..4], [Transfer::Infect as u8, 3, 9, 4]);
+        assert_eq!(virus.pointer(), 2);
+        assert_eq!(virus.transfer(&mut host), None);
+
+        let mut a = genome(&[Transfer::Conjugate as u8, 2, 9]);
+        let mut b = genome(&[1, 2, 3]);
+        assert_eq!(a.transfer(&mut b), Some(Transfer::Conjugate));
+        assert_eq!(a.genes()[..3], [1, 2, 9]);
+        assert_eq!(b.genes()[..3], [Transfer::Conjugate as u8, 2, 3]);
+    }
+
+    #[test]
+    fn test_offspring() {
+        use rand::SeedableRng;
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let mut parent = Genome::random(&mut rng);
+        parent.pointer = 7;
+        let child = parent.offspring(&mut rng, 0.0);
+        assert_eq!((child.genes, child.pointer), (parent.genes, 0));
+        for _ in 0..100 {
+            let child = parent.offspring(&mut rng, 1.0);
+            assert!(distance(&parent, &child) <= 1);
+            assert!(child
+                .genes()
+                .iter()
+                .all(|&gene| (gene as usize) < genome::SIZE));
+        }
+        let mutated = (0..100)
+            .filter(|_| parent.offspring(&mut rng, 1.0) != parent.offspring(&mut rng, 0.0))
+            .count();
+        assert!(mutated > 90, "{mutated}");
+    }
+
+    #[test]
+    fn test_serde() {
+        let mut genome = genome(&[1, 2, 3]);
+        genome.pointer = 5;
+        let json = serde_json::to_value(&genome).unwrap();
+        assert_eq!(
+            serde_json::from_value::<Genome>(json.clone()).unwrap(),
+            genome
+        );
+
+        let mut short = json.clone();
+        short["genes"].as_array_mut().unwrap().pop();
+        let error = serde_json::from_value::<Genome>(short).unwrap_err();
+        assert_eq!(error.to_string(), "Genome has 63 genes instead of 64");
+        let mut pointer = json;
+        pointer["pointer"] = genome::SIZE.into();
+        let error = serde_json::from_value::<Genome>(pointer).unwrap_err();
+        assert_eq!(error.to_string(), "Genome pointer 64 is out of 64 genes");
+    }
+
+    #[test]
+    fn test_transfer_counter() {
+        let mut counter = TransferCounter::default();
+        counter.record(Transfer::Infect);
+        counter.record(Transfer::Conjugate);
+        counter.record(Transfer::Infect);
+        assert_eq!(
+            (counter.infections, counter.conjugations, counter.total()),
+            (2, 1, 3)
+        );
+    }
+}
@@ -1,18 +1,19 @@
-use std::ops::{Add, AddAssign};
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 
-#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Deserialize, Serialize, Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub struct Point<T: Copy> {
     pub x: T,
     pub y: T,
 }
 
-#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Deserialize, Serialize, Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub struct Size<T: Copy> {
     pub w: T,
     pub h: T,
 }
 
-#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Deserialize, Serialize, Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub struct Rect<T: Copy> {
     pub top_left: Point<T>,
     pub size: Size<T>,
@@ -105,3 +106,446 @@ where
         }
     }
 }
+
+/// Addition which stops at the bounds of the type instead of overflowing.
+pub trait SaturatingAdd: Copy {
+    fn saturating_add(self, rhs: Self) -> Self;
+}
+
+macro_rules! impl_saturating_add {
+    ($($t:ty),*) => {
+        $(impl SaturatingAdd for $t {
+            fn saturating_add(self, rhs: Self) -> Self {
+                <$t>::saturating_add(self, rhs)
+            }
+        })*
+    };
+}
+
+impl_saturating_add!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl<T: SaturatingAdd> Rect<T> {
+    /// Same as [`Rect::bottom_right`], but stops at the maximum value instead of overflowing
+    pub fn saturating_bottom_right(&self) -> Point<T> {
+        Point {
+            x: self.top_left.x.saturating_add(self.size.w),
+            y: self.top_left.y.saturating_add(self.size.h),
+        }
+    }
+}
+
+impl<T> Rect<T>
+where
+    T: SaturatingAdd + Sub<Output = T> + Ord + Default + Copy,
+{
+    /// Common part of both rects, `None` if it is empty
+    pub fn intersection(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        let (bottom_right, other_bottom_right) = (
+            self.saturating_bottom_right(),
+            other.saturating_bottom_right(),
+        );
+        let top_left = Point {
+            x: self.top_left.x.max(other.top_left.x),
+            y: self.top_left.y.max(other.top_left.y),
+        };
+        let bottom_right = Point {
+            x: bottom_right.x.min(other_bottom_right.x),
+            y: bottom_right.y.min(other_bottom_right.y),
+        };
+        if top_left.x >= bottom_right.x || top_left.y >= bottom_right.y {
+            return None;
+        }
+        Some(Rect {
+            top_left,
+            size: Size {
+                w: bottom_right.x - top_left.x,
+                h: bottom_right.y - top_left.y,
+            },
+        })
+    }
+}
+
+impl<T> SubAssign for Point<T>
+where
+    T: SubAssign + Copy,
+{
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+impl<T> Sub for Point<T>
+where
+    T: Sub<Output = T> + Copy,
+{
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::Output {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl<T> Neg for Point<T>
+where
+    T: Neg<Output = T> + Copy,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::Output {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl<T> Mul<T> for Point<T>
+where
+    T: Mul<Output = T> + Copy,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self::Output {
+        Self::Output {
+            x: self.x * rhs,
+            y: self.y * rhs,
+        }
+    }
+}
+
+impl<T> Point<T>
+where
+    T: Add<Output = T> + Sub<Output = T> + PartialOrd + Copy,
+{
+    pub fn manhattan_distance(&self, other: Point<T>) -> T {
+        let (dx, dy) = self.abs_diff(other);
+        dx + dy
+    }
+
+    pub fn chebyshev_distance(&self, other: Point<T>) -> T {
+        let (dx, dy) = self.abs_diff(other);
+        if dx > dy {
+            dx
+        } else {
+            dy
+        }
+    }
+
+    fn abs_diff(&self, other: Point<T>) -> (T, T) {
+        let abs_diff = |a: T, b: T| if a > b { a - b } else { b - a };
+        (abs_diff(self.x, other.x), abs_diff(self.y, other.y))
+    }
+}
+
+impl TryFrom<Point<i32>> for Point<usize> {
+    type Error = ();
+
+    fn try_from(point: Point<i32>) -> Result<Self, Self::Error> {
+        Ok(Point {
+            x: point.x.try_into().map_err(|_| ())?,
+            y: point.y.try_into().map_err(|_| ())?,
+        })
+    }
+}
+
+impl TryFrom<Point<usize>> for Point<i32> {
+    type Error = ();
+
+    fn try_from(point: Point<usize>) -> Result<Self, Self::Error> {
+        Ok(Point {
+            x: point.x.try_into().map_err(|_| ())?,
+            y: point.y.try_into().map_err(|_| ())?,
+        })
+    }
+}
+
+impl From<Point<f32>> for egui::Pos2 {
+    fn from(point: Point<f32>) -> Self {
+        egui::pos2(point.x, point.y)
+    }
+}
+
+impl From<egui::Pos2> for Point<f32> {
+    fn from(pos: egui::Pos2) -> Self {
+        Point { x: pos.x, y: pos.y }
+    }
+}
+
+/// Cell containing the position: coordinates are rounded down, values beyond the range of `i32`
+/// saturate to its bounds and NaN becomes 0.
+impl From<egui::Pos2> for Point<i32> {
+    fn from(pos: egui::Pos2) -> Self {
+        // `as` casts of floats saturate and map NaN to 0
+        Point {
+            x: pos.x.floor() as i32,
+            y: pos.y.floor() as i32,
+        }
+    }
+}
+
+/// Cell containing the position: coordinates are rounded down, negative values and NaN are
+/// clamped to 0 and values beyond the range of `usize` to `usize::MAX`.
+impl From<egui::Pos2> for Point<usize> {
+    fn from(pos: egui::Pos2) -> Self {
+        Point {
+            x: pos.x.floor() as usize,
+            y: pos.y.floor() as usize,
+        }
+    }
+}
+
+impl From<Point<i32>> for egui::Pos2 {
+    fn from(point: Point<i32>) -> Self {
+        egui::pos2(point.x as f32, point.y as f32)
+    }
+}
+
+impl From<Point<usize>> for egui::Pos2 {
+    fn from(point: Point<usize>) -> Self {
+        egui::pos2(point.x as f32, point.y as f32)
+    }
+}
+
+impl<T> Rect<T>
+where
+    T: SaturatingAdd + Add<Output = T> + Sub<Output = T> + Ord + Default + Copy,
+{
+    /// Smallest rect which contains both rects, empty rects are ignored
+    pub fn union(&self, other: &Rect<T>) -> Rect<T> {
+        if other.is_empty() {
+            return *self;
+        }
+        if self.is_empty() {
+            return *other;
+        }
+        let (bottom_right, other_bottom_right) = (
+            self.saturating_bottom_right(),
+            other.saturating_bottom_right(),
+        );
+        let top_left = Point {
+            x: self.top_left.x.min(other.top_left.x),
+            y: self.top_left.y.min(other.top_left.y),
+        };
+        Rect {
+            top_left,
+            size: Size {
+                w: bottom_right.x.max(other_bottom_right.x) - top_left.x,
+                h: bottom_right.y.max(other_bottom_right.y) - top_left.y,
+            },
+        }
+    }
+
+    pub fn contains_rect(&self, other: &Rect<T>) -> bool {
+        let (bottom_right, other_bottom_right) = (
+            self.saturating_bottom_right(),
+            other.saturating_bottom_right(),
+        );
+        other.top_left.x >= self.top_left.x
+            && other.top_left.y >= self.top_left.y
+            && other_bottom_right.x <= bottom_right.x
+            && other_bottom_right.y <= bottom_right.y
+    }
+
+    /// Grows rect by `by` on each side, `top_left` must not underflow
+    pub fn expand(&self, by: T) -> Rect<T> {
+        Rect {
+            top_left: Point {
+                x: self.top_left.x - by,
+                y: self.top_left.y - by,
+            },
+            size: Size {
+                w: self.size.w + by + by,
+                h: self.size.h + by + by,
+            },
+        }
+    }
+}
+
+impl<T> Rect<T>
+where
+    T: Add<Output = T> + PartialOrd + From<u8> + Copy,
+{
+    /// All points of the rect in row-major order
+    pub fn points(&self) -> RectPoints<T> {
+        RectPoints {
+            next: self.top_left,
+            left: self.top_left.x,
+            bottom_right: self.bottom_right(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct RectPoints<T: Copy> {
+    next: Point<T>,
+    left: T,
+    bottom_right: Point<T>,
+}
+
+impl<T> Iterator for RectPoints<T>
+where
+    T: Add<Output = T> + PartialOrd + From<u8> + Copy,
+{
+    type Item = Point<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next.y >= self.bottom_right.y || self.left >= self.bottom_right.x {
+            return None;
+        }
+        let point = self.next;
+        self.next.x = self.next.x + T::from(1);
+        if self.next.x >= self.bottom_right.x {
+            self.next.x = self.left;
+            self.next.y = self.next.y + T::from(1);
+        }
+        Some(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersection() {
+        let rect = |x, y, w, h| Rect {
+            top_left: Point { x, y },
+            size: Size { w, h },
+        };
+        assert_eq!(
+            rect(0, 0, 4, 4).intersection(&rect(2, 1, 5, 2)),
+            Some(rect(2, 1, 2, 2))
+        );
+        assert_eq!(
+            rect(2, 1, 5, 2).intersection(&rect(0, 0, 4, 4)),
+            Some(rect(2, 1, 2, 2))
+        );
+        assert_eq!(
+            rect(0, 0, 4, 4).intersection(&rect(1, 1, 1, 1)),
+            Some(rect(1, 1, 1, 1))
+        );
+        assert_eq!(rect(0, 0, 4, 4).intersection(&rect(4, 0, 1, 1)), None);
+        assert_eq!(rect(0, 0, 4, 4).intersection(&rect(1, 1, 0, 1)), None);
+        assert_eq!(
+            rect(0, 0, 4, 4).intersection(&rect(2, 3, usize::MAX, usize::MAX)),
+            Some(rect(2, 3, 2, 1))
+        );
+        assert_eq!(
+            rect(usize::MAX - 1, 0, 5, 5).saturating_bottom_right(),
+            Point {
+                x: usize::MAX,
+                y: 5
+            }
+        );
+    }
+
+    #[test]
+    fn test_point_arithmetic() {
+        let a = Point { x: 3, y: -2 };
+        let b = Point { x: -1, y: 5 };
+        assert_eq!(a - b, Point { x: 4, y: -7 });
+        assert_eq!(-a, Point { x: -3, y: 2 });
+        assert_eq!(a * 2, Point { x: 6, y: -4 });
+        assert_eq!(a.manhattan_distance(b), 11);
+        assert_eq!(a.chebyshev_distance(b), 7);
+        let (c, d) = (Point { x: 1usize, y: 7 }, Point { x: 4usize, y: 2 });
+        assert_eq!(c.manhattan_distance(d), 8);
+        assert_eq!(d.chebyshev_distance(c), 5);
+    }
+
+    #[test]
+    fn test_point_conversions() {
+        assert_eq!(
+            Point::<usize>::try_from(Point { x: 1, y: 2 }),
+            Ok(Point { x: 1, y: 2 })
+        );
+        assert_eq!(Point::<usize>::try_from(Point { x: -1, y: 2 }), Err(()));
+        assert_eq!(
+            Point::<i32>::try_from(Point {
+                x: usize::MAX,
+                y: 2
+            }),
+            Err(())
+        );
+        assert_eq!(
+            egui::Pos2::from(Point { x: 1usize, y: 2 }),
+            egui::pos2(1.0, 2.0)
+        );
+        assert_eq!(
+            Point::from(egui::pos2(1.5, 2.0)),
+            Point { x: 1.5f32, y: 2.0 }
+        );
+    }
+
+    #[test]
+    fn test_pos2_to_cell() {
+        use egui::pos2;
+        let cell = |x, y| Point::<i32>::from(pos2(x, y));
+        assert_eq!(cell(1.9, 2.0), Point { x: 1, y: 2 });
+        assert_eq!(cell(-0.5, -2.0), Point { x: -1, y: -2 });
+        assert_eq!(
+            cell(1e10, -1e10),
+            Point {
+                x: i32::MAX,
+                y: i32::MIN
+            }
+        );
+        assert_eq!(cell(f32::NAN, f32::INFINITY), Point { x: 0, y: i32::MAX });
+        let cell = |x, y| Point::<usize>::from(pos2(x, y));
+        assert_eq!(cell(1.9, 2.0), Point { x: 1, y: 2 });
+        assert_eq!(cell(-0.5, -2.0), Point { x: 0, y: 0 });
+        assert_eq!(
+            cell(f32::NAN, 1e30),
+            Point {
+                x: 0,
+                y: usize::MAX
+            }
+        );
+        assert_eq!(
+            cell(f32::NEG_INFINITY, f32::INFINITY),
+            Point {
+                x: 0,
+                y: usize::MAX
+            }
+        );
+    }
+
+    #[test]
+    fn test_rect_operations() {
+        let rect = |x, y, w, h| Rect {
+            top_left: Point { x, y },
+            size: Size { w, h },
+        };
+        assert_eq!(rect(0, 0, 2, 2).union(&rect(3, 1, 1, 3)), rect(0, 0, 4, 4));
+        assert_eq!(rect(0, 0, 2, 2).union(&rect(9, 9, 0, 3)), rect(0, 0, 2, 2));
+        assert!(rect(0, 0, 4, 4).contains_rect(&rect(1, 1, 3, 3)));
+        assert!(!rect(0, 0, 4, 4).contains_rect(&rect(1, 1, 4, 3)));
+        assert_eq!(rect(2, 2, 1, 1).expand(2), rect(0, 0, 5, 5));
+    }
+
+    #[test]
+    fn test_rect_points() {
+        let rect = Rect {
+            top_left: Point { x: 1, y: 2 },
+            size: Size { w: 2, h: 2 },
+        };
+        assert_eq!(
+            rect.points().collect::<Vec<_>>(),
+            [
+                Point { x: 1, y: 2 },
+                Point { x: 2, y: 2 },
+                Point { x: 1, y: 3 },
+                Point { x: 2, y: 3 }
+            ]
+        );
+        let empty = Rect {
+            top_left: Point { x: 1, y: 2 },
+            size: Size { w: 0, h: 2 },
+        };
+        assert_eq!(empty.points().count(), 0);
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+
+/// Undo and redo stacks of actions, the oldest actions are dropped over the limit.
+///
+/// Actions are applied by the caller, the history only keeps their order.
+#[derive(Clone, PartialEq, Debug)]
+pub struct History<A> {
+    undo: VecDeque<A>,
+    redo: Vec<A>,
+    limit: usize,
+}
+
+impl<A> History<A> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Records a performed action, clears the redo stack
+    pub fn push(&mut self, action: A) {
+        if self.undo.len() == self.limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(action);
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Returns the action to revert
+    pub fn undo(&mut self) -> Option<&A> {
+        let action = self.undo.pop_back()?;
+        self.redo.push(action);
+        self.redo.last()
+    }
+
+    /// Returns the action to perform again
+    pub fn redo(&mut self) -> Option<&A> {
+        let action = self.redo.pop()?;
+        self.undo.push_back(action);
+        self.undo.back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::new(10);
+        history.push(1);
+        history.push(2);
+        assert_eq!(history.undo(), Some(&2));
+        assert_eq!(history.undo(), Some(&1));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), Some(&1));
+        history.push(3);
+        assert!(!history.can_redo());
+        assert_eq!(history.undo(), Some(&3));
+        assert_eq!(history.undo(), Some(&1));
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut history = History::new(2);
+        for action in 1..=3 {
+            history.push(action);
+        }
+        assert_eq!(history.undo(), Some(&3));
+        assert_eq!(history.undo(), Some(&2));
+        assert!(!history.can_undo());
+    }
+}
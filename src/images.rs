@@ -1,8 +1,63 @@
 use crate::bot::Direction;
 use crate::consts::{drawing, field};
 use crate::{draw, Point, Rect, Size};
-use egui::{Color32, ColorImage};
+use egui::{pos2, Color32, ColorImage};
+use serde::{Deserialize, Serialize};
 use std::array;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Supported sizes of a cell sprite.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum CellSize {
+    Eight,
+    Sixteen,
+    #[default]
+    ThirtyTwo,
+    SixtyFour,
+}
+
+impl CellSize {
+    pub const ALL: [CellSize; 4] = [
+        CellSize::Eight,
+        CellSize::Sixteen,
+        CellSize::ThirtyTwo,
+        CellSize::SixtyFour,
+    ];
+
+    pub fn px(self) -> usize {
+        match self {
+            CellSize::Eight => 8,
+            CellSize::Sixteen => 16,
+            CellSize::ThirtyTwo => field::CELL_SIZE,
+            CellSize::SixtyFour => 64,
+        }
+    }
+
+    /// Next larger size, the largest one stays
+    pub fn larger(self) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|&size| size == self)
+            .unwrap_or_default();
+        Self::ALL[(i + 1).min(Self::ALL.len() - 1)]
+    }
+
+    /// Next smaller size, the smallest one stays
+    pub fn smaller(self) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|&size| size == self)
+            .unwrap_or_default();
+        Self::ALL[i.saturating_sub(1)]
+    }
+}
+
+impl Display for CellSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} px", self.px())
+    }
+}
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct Images {
@@ -20,42 +75,56 @@ pub struct BotImage {
 
 impl Default for Images {
     fn default() -> Self {
-        Self {
-            apple: create_apple(),
-            organics: create_organics(),
-            rock: create_rock(),
-            bot: BotImage::default(),
-        }
+        Self::with_cell_size(CellSize::default())
     }
 }
 
-fn create_apple() -> ColorImage {
-    let mut apple = create_empty_square(field::CELL_SIZE);
-    for y in 0..apple.height() {
-        let y_dist = field::CELL_SIZE_HALF as isize - y as isize;
-        let y_dist_2 = (y_dist * y_dist) as usize;
-
-        for x in 0..apple.width() {
-            let x_dist = field::CELL_SIZE_HALF as isize - x as isize;
-            let x_dist_2 = (x_dist * x_dist) as usize;
+impl Images {
+    pub fn with_cell_size(cell_size: CellSize) -> Self {
+        let cell_size = cell_size.px();
+        Self {
+            apple: create_apple(cell_size),
+            organics: create_organics(cell_size),
+            rock: create_rock(cell_size),
+            bot: BotImage::with_px(cell_size),
+        }
+    }
 
-            if x_dist_2 + y_dist_2 < field::CELL_SIZE_HALF * field::CELL_SIZE_HALF {
-                apple[(x, y)] = drawing::APPLE_DRAW_COLOR_RGBA;
-            }
+    pub fn tile(&self, tile: Tile) -> &ColorImage {
+        match tile {
+            Tile::Apple => &self.apple,
+            Tile::Organics => &self.organics,
+            Tile::Rock => &self.rock,
+            Tile::BotBody => &self.bot.body,
+            Tile::BotHead(direction) => &self.bot.head[direction as usize],
         }
     }
+}
+
+fn create_apple(cell_size: usize) -> ColorImage {
+    let cell_size_half = (cell_size / 2) as i32;
+    let mut apple = create_empty_square(cell_size);
+    draw::filled_circle(
+        &mut apple,
+        Point {
+            x: cell_size_half,
+            y: cell_size_half,
+        },
+        cell_size_half,
+        drawing::APPLE_DRAW_COLOR_RGBA,
+    );
     apple
 }
 
-fn create_organics() -> ColorImage {
-    let mut organics = create_empty_square(field::CELL_SIZE);
+fn create_organics(cell_size: usize) -> ColorImage {
+    let mut organics = create_empty_square(cell_size);
     draw::rect(
         &mut organics,
         &Rect {
             top_left: Point { x: 1, y: 1 },
             size: Size {
-                w: field::CELL_SIZE - 2,
-                h: field::CELL_SIZE - 2,
+                w: cell_size - 2,
+                h: cell_size - 2,
             },
         },
         drawing::ORGANIC_WASTE_OUTLINE_COLOR,
@@ -65,8 +134,8 @@ fn create_organics() -> ColorImage {
         &Rect {
             top_left: Point { x: 2, y: 2 },
             size: Size {
-                w: field::CELL_SIZE - 4,
-                h: field::CELL_SIZE - 4,
+                w: cell_size - 4,
+                h: cell_size - 4,
             },
         },
         drawing::ORGANIC_WASTE_DRAW_COLOR,
@@ -74,15 +143,15 @@ fn create_organics() -> ColorImage {
     organics
 }
 
-fn create_rock() -> ColorImage {
-    let mut rock = create_empty_square(field::CELL_SIZE);
+fn create_rock(cell_size: usize) -> ColorImage {
+    let mut rock = create_empty_square(cell_size);
     draw::filled_rect(
         &mut rock,
         &Rect {
             top_left: Point { x: 1, y: 1 },
             size: Size {
-                w: field::CELL_SIZE - 2,
-                h: field::CELL_SIZE - 2,
+                w: cell_size - 2,
+                h: cell_size - 2,
             },
         },
         drawing::ROCK_DRAW_COLOR,
@@ -92,36 +161,65 @@ fn create_rock() -> ColorImage {
 
 impl Default for BotImage {
     fn default() -> Self {
+        Self::with_cell_size(CellSize::default())
+    }
+}
+
+impl BotImage {
+    pub fn with_cell_size(cell_size: CellSize) -> Self {
+        Self::with_px(cell_size.px())
+    }
+
+    fn with_px(cell_size: usize) -> Self {
+        let cell_size_half = cell_size / 2;
         let head = array::from_fn(|i| {
-            let mut head = create_empty_square(field::CELL_SIZE);
+            let mut head = create_empty_square(cell_size);
             if let Some(color) = drawing::BOT_OUTLINE_COLOR {
                 draw::rect(
                     &mut head,
                     &Rect {
                         top_left: Point { x: 0, y: 0 },
                         size: Size {
-                            w: field::CELL_SIZE,
-                            h: field::CELL_SIZE,
+                            w: cell_size,
+                            h: cell_size,
                         },
                     },
                     color,
                 );
             }
             if let Some(color) = drawing::BOT_HEAD_COLOR {
+                let head_width = cell_size / field::CELL_SIZE;
+                if head_width > 1 {
+                    // One pixel wide line is hardly visible on large sprites
+                    let center = cell_size_half as f32;
+                    let shift = Point::<i32>::from(Direction::try_from(i as u32).unwrap());
+                    draw::thick_line(
+                        &mut head,
+                        Point {
+                            x: center,
+                            y: center,
+                        },
+                        Point {
+                            x: center * (1 + shift.x) as f32,
+                            y: center * (1 + shift.y) as f32,
+                        },
+                        head_width as f32,
+                        true,
+                        color,
+                    );
+                    return head;
+                }
                 let half_size = Size {
-                    w: field::CELL_SIZE_HALF,
-                    h: field::CELL_SIZE_HALF,
+                    w: cell_size_half,
+                    h: cell_size_half,
                 };
-                let range_to_center = 0..field::CELL_SIZE_HALF;
-                let range_from_center = field::CELL_SIZE_HALF..field::CELL_SIZE;
+                let range_to_center = 0..cell_size_half;
+                let range_from_center = cell_size_half..cell_size;
                 let i = Direction::try_from(i as u32).unwrap();
                 match i {
-                    Direction::N => draw::vertical_line(
-                        &mut head,
-                        field::CELL_SIZE_HALF,
-                        range_to_center,
-                        color,
-                    ),
+                    Direction::N => {
+                        draw::vertical_line(&mut head, cell_size_half, range_to_center, color)
+                    }
                     Direction::NW => draw::diagonal_line_from_top_left(
                         &mut head,
                         &Rect {
@@ -130,51 +228,42 @@ impl Default for BotImage {
                         },
                         color,
                     ),
-                    Direction::W => draw::horizontal_line(
-                        &mut head,
-                        range_to_center,
-                        field::CELL_SIZE_HALF,
-                        color,
-                    ),
+                    Direction::W => {
+                        draw::horizontal_line(&mut head, range_to_center, cell_size_half, color)
+                    }
                     Direction::SW => draw::diagonal_line_from_bottom_left(
                         &mut head,
                         &Rect {
                             top_left: Point {
                                 x: 0,
-                                y: field::CELL_SIZE_HALF,
+                                y: cell_size_half,
                             },
                             size: half_size,
                         },
                         color,
                     ),
-                    Direction::S => draw::vertical_line(
-                        &mut head,
-                        field::CELL_SIZE_HALF,
-                        range_from_center,
-                        color,
-                    ),
+                    Direction::S => {
+                        draw::vertical_line(&mut head, cell_size_half, range_from_center, color)
+                    }
                     Direction::SE => draw::diagonal_line_from_top_left(
                         &mut head,
                         &Rect {
                             top_left: Point {
-                                x: field::CELL_SIZE_HALF,
-                                y: field::CELL_SIZE_HALF,
+                                x: cell_size_half,
+                                y: cell_size_half,
                             },
                             size: half_size,
                         },
                         color,
                     ),
-                    Direction::E => draw::horizontal_line(
-                        &mut head,
-                        range_from_center,
-                        field::CELL_SIZE_HALF,
-                        color,
-                    ),
+                    Direction::E => {
+                        draw::horizontal_line(&mut head, range_from_center, cell_size_half, color)
+                    }
                     Direction::NE => draw::diagonal_line_from_bottom_left(
                         &mut head,
                         &Rect {
                             top_left: Point {
-                                x: field::CELL_SIZE_HALF,
+                                x: cell_size_half,
                                 y: 0,
                             },
                             size: half_size,
@@ -185,11 +274,112 @@ impl Default for BotImage {
             }
             head
         });
-        let body = ColorImage::new([field::CELL_SIZE, field::CELL_SIZE], Color32::WHITE);
+        let body = ColorImage::new([cell_size, cell_size], Color32::WHITE);
         Self { head, body }
     }
 }
 
+/// Single sprite of a cell.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Tile {
+    Apple,
+    Organics,
+    Rock,
+    BotBody,
+    BotHead(Direction),
+}
+
+impl Tile {
+    pub const COUNT: usize = 12;
+    pub const ALL: [Tile; Tile::COUNT] = [
+        Tile::Apple,
+        Tile::Organics,
+        Tile::Rock,
+        Tile::BotBody,
+        Tile::BotHead(Direction::N),
+        Tile::BotHead(Direction::NW),
+        Tile::BotHead(Direction::W),
+        Tile::BotHead(Direction::SW),
+        Tile::BotHead(Direction::S),
+        Tile::BotHead(Direction::SE),
+        Tile::BotHead(Direction::E),
+        Tile::BotHead(Direction::NE),
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            Tile::Apple => 0,
+            Tile::Organics => 1,
+            Tile::Rock => 2,
+            Tile::BotBody => 3,
+            Tile::BotHead(direction) => 4 + direction as usize,
+        }
+    }
+}
+
+/// Layout of all tiles packed into a single image, row by row.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Atlas {
+    pub cell_size: usize,
+}
+
+impl Atlas {
+    const COLUMNS: usize = 4;
+    const ROWS: usize = Tile::COUNT.div_ceil(Self::COLUMNS);
+
+    pub fn size(&self) -> Size<usize> {
+        Size {
+            w: Self::COLUMNS * self.cell_size,
+            h: Self::ROWS * self.cell_size,
+        }
+    }
+
+    /// Position of the tile in the atlas, in pixels
+    pub fn rect(&self, tile: Tile) -> Rect<usize> {
+        let index = tile.index();
+        Rect {
+            top_left: Point {
+                x: index % Self::COLUMNS * self.cell_size,
+                y: index / Self::COLUMNS * self.cell_size,
+            },
+            size: Size {
+                w: self.cell_size,
+                h: self.cell_size,
+            },
+        }
+    }
+
+    /// Normalized texture coordinates of the tile
+    pub fn uv(&self, tile: Tile) -> egui::Rect {
+        let rect = self.rect(tile);
+        let bottom_right = rect.bottom_right();
+        let size = self.size();
+        let (w, h) = (size.w as f32, size.h as f32);
+        egui::Rect::from_min_max(
+            pos2(rect.top_left.x as f32 / w, rect.top_left.y as f32 / h),
+            pos2(bottom_right.x as f32 / w, bottom_right.y as f32 / h),
+        )
+    }
+
+    /// Packs sprites of `images` into a single image, `images` must have the atlas cell size
+    pub fn pack(&self, images: &Images) -> ColorImage {
+        let size = self.size();
+        let mut atlas = ColorImage::new([size.w, size.h], Color32::TRANSPARENT);
+        let mut put = |tile: Tile, image: &ColorImage| {
+            let top_left = self.rect(tile).top_left;
+            for y in 0..self.cell_size {
+                for x in 0..self.cell_size {
+                    atlas[(top_left.x + x, top_left.y + y)] = image[(x, y)];
+                }
+            }
+        };
+        for tile in Tile::ALL {
+            put(tile, images.tile(tile));
+        }
+        atlas
+    }
+}
+
 fn create_empty_square(size: usize) -> ColorImage {
     ColorImage::new([size, size], Color32::TRANSPARENT)
 }
@@ -200,7 +390,7 @@ mod tests {
 
     #[test]
     fn test_organics() {
-        let organics = create_organics();
+        let organics = create_organics(field::CELL_SIZE);
         assert_eq!(organics[(0, 0)], Color32::TRANSPARENT);
         assert_eq!(
             organics[(organics.width() - 1, organics.height() - 1)],
@@ -217,4 +407,73 @@ mod tests {
             drawing::ORGANIC_WASTE_DRAW_COLOR
         );
     }
+
+    #[test]
+    fn test_apple() {
+        let apple = create_apple(field::CELL_SIZE);
+        let half = field::CELL_SIZE_HALF as isize;
+        for y in 0..apple.height() {
+            for x in 0..apple.width() {
+                let (dx, dy) = (half - x as isize, half - y as isize);
+                assert_eq!(
+                    apple[(x, y)],
+                    if dx * dx + dy * dy < half * half {
+                        drawing::APPLE_DRAW_COLOR_RGBA
+                    } else {
+                        Color32::TRANSPARENT
+                    },
+                    "at {x}, {y}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_cell_size_zoom() {
+        assert_eq!(CellSize::Sixteen.larger(), CellSize::ThirtyTwo);
+        assert_eq!(CellSize::SixtyFour.larger(), CellSize::SixtyFour);
+        assert_eq!(CellSize::Sixteen.smaller(), CellSize::Eight);
+        assert_eq!(CellSize::Eight.smaller(), CellSize::Eight);
+    }
+
+    #[test]
+    fn test_with_cell_size() {
+        for cell_size in CellSize::ALL {
+            let px = cell_size.px();
+            let images = Images::with_cell_size(cell_size);
+            for image in [
+                &images.apple,
+                &images.organics,
+                &images.rock,
+                &images.bot.body,
+            ]
+            .into_iter()
+            .chain(&images.bot.head)
+            {
+                assert_eq!(image.size, [px, px], "{cell_size}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_atlas() {
+        let images = Images::with_cell_size(CellSize::Eight);
+        let atlas = Atlas { cell_size: 8 };
+        let image = atlas.pack(&images);
+        assert_eq!(image.size, [32, 24]);
+        let rect = atlas.rect(Tile::BotHead(Direction::E));
+        for y in 0..8 {
+            for x in 0..8 {
+                assert_eq!(
+                    image[(rect.top_left.x + x, rect.top_left.y + y)],
+                    images.bot.head[Direction::E as usize][(x, y)],
+                    "at {x}, {y}"
+                );
+            }
+        }
+        assert_eq!(
+            atlas.uv(Tile::Apple),
+            egui::Rect::from_min_max(pos2(0.0, 0.0), pos2(0.25, 1.0 / 3.0))
+        );
+    }
 }
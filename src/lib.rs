@@ -1,11 +1,27 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+pub mod autosave;
 pub mod bot;
+pub mod camera;
+pub mod colony;
+pub mod commands;
+pub mod config;
 pub mod consts;
 pub mod draw;
+pub mod editor;
+pub mod export;
+pub mod genome;
 mod geom;
+pub mod history;
 pub mod images;
+pub mod map;
+pub mod render;
+pub mod replay;
+pub mod settings;
+pub mod share;
+pub mod timeline;
+pub mod world;
 
 pub use app::TemplateApp;
-pub use geom::{Point, Rect, Size};
+pub use geom::{Point, Rect, RectPoints, SaturatingAdd, Size};
@@ -6,6 +6,19 @@
 fn main() -> eframe::Result {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        let result = match args[0].as_str() {
+            "--replay" => replay(&args[1..]),
+            _ => time_lapse(&args),
+        };
+        if let Err(e) = result {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+        return Ok(());
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
@@ -24,6 +37,81 @@ fn main() -> eframe::Result {
     )
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+const USAGE: &str =
+    "Usage: biobots [--time-lapse DIR [--ticks N] [--every N] [--seed TEXT] [--cell-size PX]]
+       biobots --replay FILE
+Without arguments opens the window. With them records a time-lapse of a world
+generated by the default rules, or plays a replay saved from the app and checks
+that it reproduces the recorded run, without a window.";
+
+/// Plays a replay file, reporting the first tick which differs from the recorded run
+#[cfg(not(target_arch = "wasm32"))]
+fn replay(args: &[String]) -> Result<(), String> {
+    use biobots::replay::WorldReplay;
+
+    let [path] = args else {
+        return Err("Expected one replay file".to_string());
+    };
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {path}: {e}"))?;
+    let replay = WorldReplay::read(std::io::BufReader::new(file))
+        .map_err(|e| format!("Failed to read {path}: {e}"))?;
+    let world = replay.play_world().map_err(|e| e.to_string())?;
+    println!(
+        "Replay of {} ticks reproduced, {} bots at the end",
+        replay.ticks(),
+        world.population()
+    );
+    Ok(())
+}
+
+/// Records a time-lapse by command line arguments
+#[cfg(not(target_arch = "wasm32"))]
+fn time_lapse(args: &[String]) -> Result<(), String> {
+    use biobots::export::TimeLapse;
+    use biobots::images::CellSize;
+
+    let mut dir = None;
+    let mut ticks = 1000;
+    let mut interval = 10;
+    let mut seed = String::new();
+    let mut cell_size = CellSize::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Missing value of {arg}"));
+        let number = |value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|e| format!("Bad value of {arg}: {e}"))
+        };
+        match arg.as_str() {
+            "--time-lapse" => dir = Some(value()?.clone()),
+            "--ticks" => ticks = number(value()?)?,
+            "--every" => interval = number(value()?)?,
+            "--seed" => seed = value()?.clone(),
+            "--cell-size" => {
+                let px = number(value()?)?;
+                cell_size = CellSize::ALL
+                    .into_iter()
+                    .find(|size| size.px() as u64 == px)
+                    .ok_or_else(|| {
+                        format!(
+                            "Cell size must be one of {:?}",
+                            CellSize::ALL.map(CellSize::px)
+                        )
+                    })?;
+            }
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    let dir = dir.ok_or("Missing --time-lapse")?;
+    let mut time_lapse = TimeLapse::new(&dir, interval);
+    biobots::export::record_time_lapse(&seed, ticks, &mut time_lapse, cell_size)
+        .map_err(|e| format!("Failed to record time-lapse to {dir}: {e}"))?;
+    println!("{} frames written to {dir}", time_lapse.frames());
+    Ok(())
+}
+
 // When compiling to web using trunk:
 #[cfg(target_arch = "wasm32")]
 fn main() {
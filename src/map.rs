@@ -0,0 +1,238 @@
+use crate::bot::Direction;
+use crate::colony::Links;
+use crate::consts::drawing;
+use crate::images::Tile;
+use crate::{Point, Rect, Size};
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Ground of a cell.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum Terrain {
+    #[default]
+    Land,
+    Mud,
+    Ocean,
+    GreenWater,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 4] = [
+        Terrain::Land,
+        Terrain::Mud,
+        Terrain::Ocean,
+        Terrain::GreenWater,
+    ];
+
+    pub fn color(self) -> Color32 {
+        match self {
+            Terrain::Land => drawing::FIELD_BACKGROUND_COLOR,
+            Terrain::Mud => drawing::MUD_COLOR,
+            Terrain::Ocean => drawing::OCEAN_COLOR,
+            Terrain::GreenWater => drawing::GREEN_WATER,
+        }
+    }
+
+    /// Bots can not step into water
+    pub fn is_water(self) -> bool {
+        matches!(self, Terrain::Ocean | Terrain::GreenWater)
+    }
+}
+
+impl Display for Terrain {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Terrain::Land => "land",
+                Terrain::Mud => "mud",
+                Terrain::Ocean => "ocean",
+                Terrain::GreenWater => "green water",
+            }
+        )
+    }
+}
+
+/// What occupies a cell.
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Object {
+    Rock,
+    Apple,
+    Organics,
+    Bot {
+        head: Direction,
+        color: Color32,
+        /// Neighbours of the same colony
+        #[serde(default)]
+        links: Links,
+    },
+}
+
+impl Object {
+    /// Tiles drawn bottom to top with their tints
+    pub fn tiles(self) -> Vec<(Tile, Color32)> {
+        match self {
+            Object::Rock => vec![(Tile::Rock, Color32::WHITE)],
+            Object::Apple => vec![(Tile::Apple, Color32::WHITE)],
+            Object::Organics => vec![(Tile::Organics, Color32::WHITE)],
+            Object::Bot { head, color, .. } => {
+                vec![
+                    (Tile::BotBody, color),
+                    (Tile::BotHead(head), Color32::WHITE),
+                ]
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Cell {
+    pub terrain: Terrain,
+    pub object: Option<Object>,
+}
+
+/// Grid of cells, row by row.
+#[derive(Deserialize, Serialize, Clone, Eq, PartialEq, Hash, Debug)]
+#[serde(try_from = "RawMap")]
+pub struct Map {
+    size: Size<usize>,
+    cells: Vec<Cell>,
+}
+
+/// Deserialized map before its cells are checked to fill the size.
+#[derive(Deserialize)]
+struct RawMap {
+    size: Size<usize>,
+    cells: Vec<Cell>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct WrongCellCount {
+    pub size: Size<usize>,
+    pub count: usize,
+}
+
+impl Display for WrongCellCount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Map of {}x{} cells has {} cells",
+            self.size.w, self.size.h, self.count
+        )
+    }
+}
+
+impl std::error::Error for WrongCellCount {}
+
+impl TryFrom<RawMap> for Map {
+    type Error = WrongCellCount;
+
+    fn try_from(RawMap { size, cells }: RawMap) -> Result<Self, Self::Error> {
+        if size.w.checked_mul(size.h) != Some(cells.len()) {
+            return Err(WrongCellCount {
+                size,
+                count: cells.len(),
+            });
+        }
+        Ok(Self { size, cells })
+    }
+}
+
+impl Map {
+    pub fn new(size: Size<usize>) -> Self {
+        Self {
+            size,
+            cells: vec![Cell::default(); size.w * size.h],
+        }
+    }
+
+    pub fn size(&self) -> Size<usize> {
+        self.size
+    }
+
+    pub fn rect(&self) -> Rect<usize> {
+        Rect {
+            top_left: Point { x: 0, y: 0 },
+            size: self.size,
+        }
+    }
+
+    fn index(&self, point: Point<usize>) -> Option<usize> {
+        self.rect()
+            .is_in_bounds(point)
+            .then_some(point.y * self.size.w + point.x)
+    }
+
+    pub fn get(&self, point: Point<usize>) -> Option<&Cell> {
+        self.index(point).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, point: Point<usize>) -> Option<&mut Cell> {
+        self.index(point).map(|i| &mut self.cells[i])
+    }
+
+    /// Returns the previous cell, or `None` if the point is out of the map
+    pub fn set(&mut self, point: Point<usize>, cell: Cell) -> Option<Cell> {
+        let i = self.index(point)?;
+        Some(std::mem::replace(&mut self.cells[i], cell))
+    }
+
+    /// Cells with their points in row-major order
+    pub fn iter(&self) -> impl Iterator<Item = (Point<usize>, &Cell)> {
+        self.rect().points().zip(&self.cells)
+    }
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        use crate::consts::field;
+        Self::new(Size {
+            w: field::WIDTH,
+            h: field::HEIGHT,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_set() {
+        let mut map = Map::new(Size { w: 3, h: 2 });
+        let rock = Cell {
+            terrain: Terrain::Mud,
+            object: Some(Object::Rock),
+        };
+        assert_eq!(map.set(Point { x: 2, y: 1 }, rock), Some(Cell::default()));
+        assert_eq!(map.set(Point { x: 3, y: 1 }, rock), None);
+        assert_eq!(map.get(Point { x: 2, y: 1 }), Some(&rock));
+        assert_eq!(map.get(Point { x: 0, y: 2 }), None);
+        assert_eq!(
+            map.iter()
+                .filter(|(_, cell)| **cell == rock)
+                .collect::<Vec<_>>(),
+            [(Point { x: 2, y: 1 }, &rock)]
+        );
+    }
+
+    #[test]
+    fn test_deserialize_checks_cell_count() {
+        let map = Map::new(Size { w: 3, h: 2 });
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(serde_json::from_str::<Map>(&json).unwrap(), map);
+
+        let mut short = serde_json::to_value(&map).unwrap();
+        short["cells"].as_array_mut().unwrap().pop();
+        let error = serde_json::from_value::<Map>(short).unwrap_err();
+        assert_eq!(error.to_string(), "Map of 3x2 cells has 5 cells");
+
+        let mut huge = serde_json::to_value(Map::new(Size { w: 0, h: 0 })).unwrap();
+        huge["size"]["w"] = usize::MAX.into();
+        huge["size"]["h"] = 2.into();
+        assert!(serde_json::from_value::<Map>(huge).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+use crate::bot::Direction;
+use crate::consts::drawing;
+use crate::images::{Atlas, Images, Tile};
+use crate::map::{Map, Terrain};
+use crate::{draw, Point, Rect, Size};
+use egui::{vec2, Color32, ColorImage, Mesh, Pos2, TextureHandle, TextureId, TextureOptions};
+
+/// How a grid of tiles is drawn.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum RenderMode {
+    /// Sprites from the atlas, see [`TileMesh`]
+    Tiles,
+    /// A few pixels per cell, see [`PixelImage`]
+    Pixels,
+}
+
+impl RenderMode {
+    /// Selects mode by the on-screen size of a cell in physical pixels
+    pub fn for_cell_size(cell_size: f32) -> Self {
+        if cell_size <= drawing::PIXEL_RENDER_MAX_CELL_SIZE {
+            RenderMode::Pixels
+        } else {
+            RenderMode::Tiles
+        }
+    }
+}
+
+/// Collects tiles of a grid into a single mesh, so the whole grid is drawn with one texture bind.
+pub struct TileMesh {
+    atlas: Atlas,
+    mesh: Mesh,
+    origin: Pos2,
+    cell_size: f32,
+}
+
+impl TileMesh {
+    /// `origin` is the screen position of cell `(0, 0)`, `cell_size` is the on-screen cell size
+    pub fn new(atlas: Atlas, texture_id: TextureId, origin: Pos2, cell_size: f32) -> Self {
+        Self {
+            atlas,
+            mesh: Mesh::with_texture(texture_id),
+            origin,
+            cell_size,
+        }
+    }
+
+    pub fn add(&mut self, cell: Point<usize>, tile: Tile, tint: Color32) {
+        let min = self.origin + vec2(cell.x as f32, cell.y as f32) * self.cell_size;
+        let rect = egui::Rect::from_min_size(min, vec2(self.cell_size, self.cell_size));
+        self.mesh.add_rect_with_uv(rect, self.atlas.uv(tile), tint);
+    }
+
+    pub fn into_mesh(self) -> Mesh {
+        self.mesh
+    }
+}
+
+/// Half of the link from the center of `cell` towards its neighbour in `direction`,
+/// so that two linked cells together draw the whole link.
+pub fn link_segment(
+    origin: Pos2,
+    cell_size: f32,
+    cell: Point<usize>,
+    direction: Direction,
+) -> [Pos2; 2] {
+    let center = origin + (vec2(cell.x as f32, cell.y as f32) + vec2(0.5, 0.5)) * cell_size;
+    let offset = Point::<i32>::from(direction);
+    [
+        center,
+        center + vec2(offset.x as f32, offset.y as f32) * (cell_size / 2.0),
+    ]
+}
+
+/// Draws a grid of tiles as plain colors, `pixels_per_cell` pixels per cell side.
+pub struct PixelImage {
+    image: ColorImage,
+    pixels_per_cell: usize,
+}
+
+impl PixelImage {
+    pub fn new(cells: Size<usize>, pixels_per_cell: usize) -> Self {
+        Self {
+            image: ColorImage::new(
+                [cells.w * pixels_per_cell, cells.h * pixels_per_cell],
+                drawing::FIELD_BACKGROUND_COLOR,
+            ),
+            pixels_per_cell,
+        }
+    }
+
+    pub fn add(&mut self, cell: Point<usize>, tile: Tile, tint: Color32) {
+        if let Some(color) = pixel_color(tile) {
+            self.fill(cell, multiply(color, tint));
+        }
+    }
+
+    /// Paints the whole cell, e.g. with the terrain color
+    pub fn fill(&mut self, cell: Point<usize>, color: Color32) {
+        draw::filled_rect(
+            &mut self.image,
+            &Rect {
+                top_left: cell * self.pixels_per_cell,
+                size: Size {
+                    w: self.pixels_per_cell,
+                    h: self.pixels_per_cell,
+                },
+            },
+            color,
+        );
+    }
+
+    pub fn into_image(self) -> ColorImage {
+        self.image
+    }
+
+    /// Uploads image into `texture`, reusing already allocated texture
+    pub fn upload<'a>(
+        self,
+        ctx: &egui::Context,
+        texture: &'a mut Option<TextureHandle>,
+    ) -> &'a TextureHandle {
+        match texture {
+            Some(texture) => {
+                texture.set(self.image, TextureOptions::NEAREST);
+                texture
+            }
+            None => texture.insert(ctx.load_texture("pixels", self.image, TextureOptions::NEAREST)),
+        }
+    }
+}
+
+/// Composes sprites of a grid of tiles into a single image, e.g. for export.
+pub struct SpriteImage<'a> {
+    images: &'a Images,
+    image: ColorImage,
+    cell_size: usize,
+}
+
+impl<'a> SpriteImage<'a> {
+    pub fn new(images: &'a Images, cells: Size<usize>) -> Self {
+        let cell_size = images.apple.width();
+        Self {
+            images,
+            image: ColorImage::new(
+                [cells.w * cell_size, cells.h * cell_size],
+                drawing::FIELD_BACKGROUND_COLOR,
+            ),
+            cell_size,
+        }
+    }
+
+    pub fn add(&mut self, cell: Point<usize>, tile: Tile, tint: Color32) {
+        let sprite = self.images.tile(tile);
+        let top_left = cell * self.cell_size;
+        for y in 0..self.cell_size {
+            for x in 0..self.cell_size {
+                draw::blend(
+                    &mut self.image,
+                    top_left + Point { x, y },
+                    multiply(sprite[(x, y)], tint),
+                );
+            }
+        }
+    }
+
+    /// Paints the whole cell, e.g. with the terrain color
+    pub fn fill(&mut self, cell: Point<usize>, color: Color32) {
+        draw::filled_rect(
+            &mut self.image,
+            &Rect {
+                top_left: cell * self.cell_size,
+                size: Size {
+                    w: self.cell_size,
+                    h: self.cell_size,
+                },
+            },
+            color,
+        );
+    }
+
+    pub fn into_image(self) -> ColorImage {
+        self.image
+    }
+}
+
+/// Terrain and objects of `map` as plain colors
+pub fn pixel_image(map: &Map, pixels_per_cell: usize) -> PixelImage {
+    let mut pixels = PixelImage::new(map.size(), pixels_per_cell);
+    for (point, cell) in map.iter() {
+        if cell.terrain != Terrain::Land {
+            pixels.fill(point, cell.terrain.color());
+        }
+        for (tile, tint) in cell.object.iter().flat_map(|object| object.tiles()) {
+            pixels.add(point, tile, tint);
+        }
+    }
+    pixels
+}
+
+/// Terrain and objects of `map` drawn with sprites from `images`
+pub fn sprite_image(images: &Images, map: &Map) -> ColorImage {
+    let mut sprites = SpriteImage::new(images, map.size());
+    for (point, cell) in map.iter() {
+        if cell.terrain != Terrain::Land {
+            sprites.fill(point, cell.terrain.color());
+        }
+        for (tile, tint) in cell.object.iter().flat_map(|object| object.tiles()) {
+            sprites.add(point, tile, tint);
+        }
+    }
+    sprites.into_image()
+}
+
+/// Color of a tile in [`RenderMode::Pixels`], bot heads are too small to be seen
+fn pixel_color(tile: Tile) -> Option<Color32> {
+    match tile {
+        Tile::Apple => Some(drawing::APPLE_DRAW_COLOR_RGBA),
+        Tile::Organics => Some(drawing::ORGANIC_WASTE_DRAW_COLOR),
+        Tile::Rock => Some(drawing::ROCK_DRAW_COLOR),
+        Tile::BotBody => Some(Color32::WHITE),
+        Tile::BotHead(_) => None,
+    }
+}
+
+/// Same as texture tinting in [`TileMesh`]
+fn multiply(color: Color32, tint: Color32) -> Color32 {
+    let channel = |a: u8, b: u8| (a as u16 * b as u16 / 255) as u8;
+    Color32::from_rgba_premultiplied(
+        channel(color.r(), tint.r()),
+        channel(color.g(), tint.g()),
+        channel(color.b(), tint.b()),
+        channel(color.a(), tint.a()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::CellSize;
+    use crate::map::Object;
+    use egui::pos2;
+
+    #[test]
+    fn test_link_segment() {
+        let origin = pos2(10.0, 20.0);
+        assert_eq!(
+            link_segment(origin, 8.0, Point { x: 1, y: 0 }, Direction::SW),
+            [pos2(22.0, 24.0), pos2(18.0, 28.0)]
+        );
+        assert_eq!(
+            link_segment(origin, 8.0, Point { x: 0, y: 1 }, Direction::NE),
+            [pos2(14.0, 32.0), pos2(18.0, 28.0)]
+        );
+    }
+
+    #[test]
+    fn test_tile_mesh() {
+        let atlas = Atlas { cell_size: 8 };
+        let mut tiles = TileMesh::new(atlas, TextureId::default(), pos2(10.0, 20.0), 16.0);
+        tiles.add(Point { x: 0, y: 0 }, Tile::Rock, Color32::WHITE);
+        tiles.add(
+            Point { x: 2, y: 1 },
+            Tile::BotHead(Direction::N),
+            Color32::WHITE,
+        );
+        let mesh = tiles.into_mesh();
+        assert_eq!(mesh.vertices.len(), 8);
+        assert_eq!(mesh.indices.len(), 12);
+        assert_eq!(
+            mesh.calc_bounds(),
+            egui::Rect::from_min_max(pos2(10.0, 20.0), pos2(58.0, 52.0))
+        );
+        assert_eq!(mesh.vertices[0].uv, atlas.uv(Tile::Rock).min);
+    }
+
+    #[test]
+    fn test_render_mode() {
+        assert_eq!(RenderMode::for_cell_size(1.0), RenderMode::Pixels);
+        assert_eq!(
+            RenderMode::for_cell_size(CellSize::Eight.px() as f32),
+            RenderMode::Pixels
+        );
+        assert_eq!(
+            RenderMode::for_cell_size(CellSize::Sixteen.px() as f32),
+            RenderMode::Tiles
+        );
+        assert_eq!(RenderMode::for_cell_size(32.0), RenderMode::Tiles);
+    }
+
+    #[test]
+    fn test_pixel_image() {
+        let mut pixels = PixelImage::new(Size { w: 3, h: 2 }, 2);
+        let tint = Color32::from_rgb(10, 20, 30);
+        pixels.add(Point { x: 1, y: 1 }, Tile::BotBody, tint);
+        pixels.add(Point { x: 1, y: 1 }, Tile::BotHead(Direction::N), tint);
+        pixels.add(Point { x: 2, y: 0 }, Tile::Rock, Color32::WHITE);
+        pixels.fill(Point { x: 0, y: 1 }, drawing::MUD_COLOR);
+        let image = pixels.into_image();
+        assert_eq!(image.size, [6, 4]);
+        for y in 0..4 {
+            for x in 0..6 {
+                let expected = match (x / 2, y / 2) {
+                    (1, 1) => tint,
+                    (2, 0) => drawing::ROCK_DRAW_COLOR,
+                    (0, 1) => drawing::MUD_COLOR,
+                    _ => drawing::FIELD_BACKGROUND_COLOR,
+                };
+                assert_eq!(image[(x, y)], expected, "at {x}, {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_sprite_image() {
+        let images = Images::with_cell_size(CellSize::Eight);
+        let mut sprites = SpriteImage::new(&images, Size { w: 2, h: 1 });
+        sprites.add(Point { x: 1, y: 0 }, Tile::Apple, Color32::WHITE);
+        let image = sprites.into_image();
+        assert_eq!(image.size, [16, 8]);
+        // transparent corner of the apple shows background
+        assert_eq!(image[(8, 0)], drawing::FIELD_BACKGROUND_COLOR);
+        assert_eq!(image[(12, 4)], drawing::APPLE_DRAW_COLOR_RGBA);
+        assert_eq!(image[(4, 4)], drawing::FIELD_BACKGROUND_COLOR);
+    }
+
+    #[test]
+    fn test_world_images() {
+        let mut map = Map::new(Size { w: 2, h: 1 });
+        map.get_mut(Point { x: 0, y: 0 }).unwrap().terrain = Terrain::Mud;
+        map.get_mut(Point { x: 1, y: 0 }).unwrap().object = Some(Object::Apple);
+
+        let image = pixel_image(&map, 1).into_image();
+        assert_eq!(
+            image.pixels,
+            [drawing::MUD_COLOR, drawing::APPLE_DRAW_COLOR_RGBA]
+        );
+
+        let images = Images::with_cell_size(CellSize::Eight);
+        let image = sprite_image(&images, &map);
+        assert_eq!(image.size, [16, 8]);
+        assert_eq!(image[(0, 0)], drawing::MUD_COLOR);
+        assert_eq!(image[(8, 0)], drawing::FIELD_BACKGROUND_COLOR);
+        assert_eq!(image[(12, 4)], drawing::APPLE_DRAW_COLOR_RGBA);
+    }
+}
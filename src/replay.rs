@@ -0,0 +1,216 @@
+use crate::config::SimConfig;
+use crate::editor::Edit;
+use crate::world::World;
+use rand_seeder::SipHasher;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::hash::Hasher;
+use std::io;
+
+/// Hash of the JSON of the world, so it is the same on all platforms
+pub fn state_hash(state: &impl Serialize) -> u64 {
+    let mut hasher = SipHasher::new();
+    let json = serde_json::to_vec(state).expect("States are serialized to JSON");
+    hasher.write(&json);
+    hasher.finish()
+}
+
+/// Recorded run: everything needed to reproduce it and the hash of the world after each tick.
+///
+/// `S` is the world snapshot, `E` is an external edit applied before a tick.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct Replay<S, E> {
+    pub initial: S,
+    pub sim_config: SimConfig,
+    pub seed: String,
+    /// Edits with the ticks they are applied before, in order
+    pub events: Vec<(u64, E)>,
+    pub hashes: Vec<u64>,
+}
+
+impl<S, E> Replay<S, E>
+where
+    S: Clone + Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    pub fn new(initial: S, sim_config: SimConfig, seed: String) -> Self {
+        Self {
+            initial,
+            sim_config,
+            seed,
+            events: Vec::new(),
+            hashes: Vec::new(),
+        }
+    }
+
+    /// Number of recorded ticks, the next event or tick gets this number
+    pub fn ticks(&self) -> u64 {
+        self.hashes.len() as u64
+    }
+
+    /// Records an edit applied before the next tick
+    pub fn record_event(&mut self, event: E) {
+        self.events.push((self.ticks(), event));
+    }
+
+    /// Records the world after a tick
+    pub fn record_tick(&mut self, state: &S) {
+        self.hashes.push(state_hash(state));
+    }
+
+    /// Replays the run from the initial snapshot. `step` applies the edits of a tick
+    /// and simulates it. Returns the final world or the first tick which differs.
+    pub fn play(&self, mut step: impl FnMut(&mut S, &[&E])) -> Result<S, Divergence> {
+        let mut state = self.initial.clone();
+        let mut events = self.events.iter().peekable();
+        for (tick, &expected) in self.hashes.iter().enumerate() {
+            let tick = tick as u64;
+            let mut edits = Vec::new();
+            while let Some((_, event)) = events.next_if(|(at, _)| *at == tick) {
+                edits.push(event);
+            }
+            step(&mut state, &edits);
+            let actual = state_hash(&state);
+            if actual != expected {
+                return Err(Divergence {
+                    tick,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(state)
+    }
+
+    pub fn write(&self, writer: impl io::Write) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, self)
+    }
+
+    pub fn read(reader: impl io::Read) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+}
+
+/// Run of the world with the map edits made during it.
+pub type WorldReplay = Replay<World, Edit>;
+
+impl WorldReplay {
+    /// Replays the run by its rules, applying the edits the way the app does
+    pub fn play_world(&self) -> Result<World, Divergence> {
+        self.play(|world, edits| {
+            for edit in edits {
+                world.edit(&self.sim_config, |map| edit.redo(map));
+            }
+            world.step(&self.sim_config);
+        })
+    }
+}
+
+/// First tick after which the replayed world differs from the recorded one.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Divergence {
+    pub tick: u64,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Replay diverged at tick {}: expected hash {:016x}, got {:016x}",
+            self.tick, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::Editor;
+    use crate::{Point, Size};
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    /// World is a counter, edits add to it
+    fn step(state: &mut u64, edits: &[&u64]) {
+        *state += 1 + edits.iter().copied().sum::<u64>();
+    }
+
+    fn record() -> Replay<u64, u64> {
+        let mut replay = Replay::new(0, SimConfig::default(), "seed".to_string());
+        let mut state = 0;
+        for tick in 0..10 {
+            let edits: Vec<u64> = if tick == 3 { vec![10, 20] } else { vec![] };
+            for &edit in &edits {
+                replay.record_event(edit);
+            }
+            step(&mut state, &edits.iter().collect::<Vec<_>>());
+            replay.record_tick(&state);
+        }
+        replay
+    }
+
+    #[test]
+    fn test_play() {
+        let replay = record();
+        assert_eq!(replay.events, [(3, 10), (3, 20)]);
+        assert_eq!(replay.play(step), Ok(40));
+    }
+
+    #[test]
+    fn test_divergence() {
+        let replay = record();
+        let result = replay.play(|state, edits| {
+            step(state, edits);
+            if *state > 35 {
+                *state += 1;
+            }
+        });
+        assert_eq!(result.map_err(|e| e.tick), Err(5));
+    }
+
+    #[test]
+    fn test_state_hash() {
+        assert_eq!(state_hash(&1usize), state_hash(&1u64));
+        assert_ne!(state_hash(&1u64), state_hash(&2u64));
+    }
+
+    #[test]
+    fn test_play_world() {
+        let config = SimConfig::default();
+        let size = Size { w: 16, h: 12 };
+        let mut world = World::generate(size, &config, Xoshiro256PlusPlus::seed_from_u64(1));
+        let mut replay = Replay::new(world.clone(), config, "seed".to_string());
+        let mut editor = Editor::default();
+        for tick in 0..20 {
+            if tick == 5 {
+                let (_, edit) = world.edit(&config, |map| {
+                    Edit::track(map, |map| {
+                        editor.paint(map, Point { x: 0, y: 0 });
+                        editor.paint(map, Point { x: 15, y: 11 });
+                    })
+                });
+                replay.record_event(edit);
+            }
+            world.step(&config);
+            replay.record_tick(&world);
+        }
+        assert_eq!(replay.play_world(), Ok(world));
+
+        replay.events.clear();
+        assert_eq!(replay.play_world().map_err(|e| e.tick), Err(5));
+    }
+
+    #[test]
+    fn test_write_read() {
+        let replay = record();
+        let mut data = Vec::new();
+        replay.write(&mut data).unwrap();
+        assert_eq!(Replay::read(data.as_slice()).unwrap(), replay);
+    }
+}
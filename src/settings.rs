@@ -0,0 +1,351 @@
+use crate::bot::Direction;
+use crate::config::{Format, SimConfig};
+use crate::editor::Editor;
+use crate::images::CellSize;
+use crate::map::Map;
+use egui::Color32;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use ron::extensions::Extensions;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Storage key of the versioned settings. Settings saved before versioning
+/// are under `eframe::APP_KEY`.
+pub const KEY: &str = "settings";
+
+/// Upgrades settings of one version to the next one
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// `MIGRATIONS[i]` upgrades settings of version `i + 1`
+const MIGRATIONS: &[Migration] = &[version_2];
+
+pub const VERSION: usize = 1 + MIGRATIONS.len();
+
+/// Version 2 keeps the map inside the simulated `world` and replaced `auto_save`
+/// of 1, 5 or 15 seconds with `auto_save_schedule`
+fn version_2(settings: &mut Value) -> Result<(), String> {
+    let fields = settings
+        .as_object_mut()
+        .ok_or("settings are not an object")?;
+    if let Some(map) = fields.remove("map") {
+        fields.insert("world".to_string(), serde_json::json!({ "map": map }));
+    }
+    let Some(auto_save) = fields.remove("auto_save") else {
+        return Ok(());
+    };
+    let seconds = match auto_save.as_str() {
+        None if auto_save.is_null() => return Ok(()),
+        Some("One") => 1,
+        Some("Five") => 5,
+        Some("Fifteen") => 15,
+        _ => return Err(format!("unknown auto_save {auto_save}")),
+    };
+    fields.insert(
+        "auto_save_schedule".to_string(),
+        serde_json::json!({ "interval": { "Seconds": seconds } }),
+    );
+    Ok(())
+}
+
+#[derive(Deserialize, Serialize)]
+struct Versioned<T> {
+    version: usize,
+    settings: T,
+}
+
+pub fn to_string<T: Serialize>(settings: &T) -> String {
+    // SAFETY: safe to call unwrap() because settings are plain serde structs with string keys
+    serde_json::to_string(&Versioned {
+        version: VERSION,
+        settings,
+    })
+    .unwrap()
+}
+
+/// Reads settings of any known version. Whatever could not be read or migrated
+/// is replaced by defaults with a logged warning, the rest is kept.
+pub fn from_str<T: DeserializeOwned + Default>(text: &str) -> T {
+    from_str_with(text, MIGRATIONS)
+}
+
+/// Settings as eframe stored the app before versioning. Fields missing in the text
+/// are left out of version 1, so the app defaults are used for them.
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+struct LegacySettings {
+    bot_head: Option<Direction>,
+    bot_color: Option<Color32>,
+    lock_initial_seed: Option<bool>,
+    initial_seed: Option<String>,
+    rng: Option<Xoshiro256PlusPlus>,
+    is_dark: Option<bool>,
+    show_settings: Option<bool>,
+    auto_save: Option<AutoSaveSec>,
+    sim_config: Option<SimConfig>,
+    config_editor: Option<LegacyConfigEditor>,
+    cell_size: Option<CellSize>,
+    map: Option<Map>,
+    editor: Option<Editor>,
+    edit_mode: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize)]
+enum AutoSaveSec {
+    One,
+    Five,
+    Fifteen,
+}
+
+#[derive(Deserialize, Serialize)]
+struct LegacyConfigEditor {
+    format: Format,
+}
+
+/// Fields of [`LegacySettings`] which hold enums
+const LEGACY_ENUM_FIELDS: &[&str] = &[
+    "bot_head",
+    "auto_save",
+    "config_editor",
+    "cell_size",
+    "map",
+    "editor",
+];
+
+/// Reads settings saved under `eframe::APP_KEY` as RON before versioning, as version 1.
+/// If they don't read as a whole, the fields which need no type names are still kept.
+pub fn from_legacy_ron<T: DeserializeOwned + Default>(text: &str) -> T {
+    // eframe stored options as plain values
+    let options = ron::Options::default().with_default_extension(Extensions::IMPLICIT_SOME);
+    let mut settings = match options.from_str::<LegacySettings>(text) {
+        // SAFETY: safe to call unwrap() because legacy settings are plain serde structs
+        Ok(legacy) => serde_json::to_value(legacy).unwrap(),
+        Err(e) => {
+            log::warn!("Legacy settings could not be read as a whole: {e}");
+            match ron::from_str::<ron::Value>(text) {
+                Ok(settings) => without_enums(ron_to_json(settings)),
+                Err(e) => {
+                    log::warn!("Legacy settings could not be read, defaults are used: {e}");
+                    return T::default();
+                }
+            }
+        }
+    };
+    // Missing fields get the app defaults
+    if let Value::Object(fields) = &mut settings {
+        fields.retain(|_, value| !value.is_null());
+    }
+    migrate(1, settings, MIGRATIONS)
+}
+
+/// RON values keep no enum variant names, so the fields with enums get defaults
+fn without_enums(mut settings: Value) -> Value {
+    if let Value::Object(fields) = &mut settings {
+        for key in LEGACY_ENUM_FIELDS {
+            if fields.remove(*key).is_some() {
+                log::warn!("Legacy setting `{key}` could not be read, default is used");
+            }
+        }
+    }
+    settings
+}
+
+fn ron_to_json(value: ron::Value) -> Value {
+    match value {
+        ron::Value::Bool(b) => Value::Bool(b),
+        ron::Value::Char(c) => Value::String(c.to_string()),
+        ron::Value::Map(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let key = match ron_to_json(key) {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    (key, ron_to_json(value))
+                })
+                .collect(),
+        ),
+        ron::Value::Number(ron::Number::Integer(i)) => Value::from(i),
+        ron::Value::Number(ron::Number::Float(f)) => Value::from(f.get()),
+        ron::Value::Option(value) => value.map_or(Value::Null, |value| ron_to_json(*value)),
+        ron::Value::String(s) => Value::String(s),
+        ron::Value::Seq(values) => Value::Array(values.into_iter().map(ron_to_json).collect()),
+        ron::Value::Unit => Value::Null,
+    }
+}
+
+fn from_str_with<T: DeserializeOwned + Default>(text: &str, migrations: &[Migration]) -> T {
+    match serde_json::from_str::<Versioned<Value>>(text) {
+        Ok(Versioned { version, settings }) => migrate(version, settings, migrations),
+        Err(e) => {
+            log::warn!("Settings could not be read, defaults are used: {e}");
+            T::default()
+        }
+    }
+}
+
+fn migrate<T: DeserializeOwned + Default>(
+    version: usize,
+    mut settings: Value,
+    migrations: &[Migration],
+) -> T {
+    if version > migrations.len() + 1 {
+        log::warn!("Settings of unknown version {version} are read as the latest version");
+    }
+    for (from, migration) in migrations.iter().enumerate().skip(version.max(1) - 1) {
+        if let Err(e) = migration(&mut settings) {
+            log::warn!(
+                "Settings could not be migrated from version {}: {e}",
+                from + 1
+            );
+        }
+    }
+    if let Ok(settings) = T::deserialize(&settings) {
+        return settings;
+    }
+    // Drop the fields which fail to read on their own, so others are kept
+    if let Value::Object(fields) = &mut settings {
+        fields.retain(|key, value| {
+            let field = Value::Object([(key.clone(), value.clone())].into_iter().collect());
+            let is_valid = T::deserialize(&field).is_ok();
+            if !is_valid {
+                log::warn!("Setting `{key}` could not be migrated, default is used");
+            }
+            is_valid
+        });
+    }
+    T::deserialize(&settings).unwrap_or_else(|e| {
+        log::warn!("Settings could not be migrated, defaults are used: {e}");
+        T::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+    #[serde(default)]
+    struct Settings {
+        seed: String,
+        size: u32,
+    }
+
+    fn rename_seed(settings: &mut Value) -> Result<(), String> {
+        let fields = settings.as_object_mut().ok_or("not an object")?;
+        if let Some(seed) = fields.remove("initial_seed") {
+            fields.insert("seed".to_string(), seed);
+        }
+        Ok(())
+    }
+
+    fn settings(seed: &str, size: u32) -> Settings {
+        Settings {
+            seed: seed.to_string(),
+            size,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let settings = settings("a", 4);
+        assert_eq!(from_str::<Settings>(&to_string(&settings)), settings);
+    }
+
+    #[test]
+    fn test_migration() {
+        let old = r#"{"version": 1, "settings": {"initial_seed": "a", "size": 4}}"#;
+        assert_eq!(
+            from_str_with::<Settings>(old, &[rename_seed]),
+            settings("a", 4)
+        );
+        // Already migrated settings are not touched
+        let new = r#"{"version": 2, "settings": {"initial_seed": "b", "seed": "a"}}"#;
+        assert_eq!(
+            from_str_with::<Settings>(new, &[rename_seed]),
+            settings("a", 0)
+        );
+    }
+
+    #[test]
+    fn test_version_2() {
+        let map = Map::new(crate::Size { w: 2, h: 1 });
+        let mut settings = serde_json::json!({ "auto_save": "Fifteen", "map": map, "size": 4 });
+        version_2(&mut settings).unwrap();
+        let world: crate::world::World = serde_json::from_value(settings["world"].clone()).unwrap();
+        assert_eq!(world.map(), &map);
+        assert_eq!(
+            settings["auto_save_schedule"],
+            serde_json::json!({ "interval": { "Seconds": 15 } })
+        );
+        assert_eq!(settings["size"], 4);
+        assert!(settings.get("map").is_none());
+        assert!(settings.get("auto_save").is_none());
+        let mut settings = serde_json::json!({ "auto_save": null });
+        version_2(&mut settings).unwrap();
+        assert_eq!(settings, serde_json::json!({}));
+        let mut settings = serde_json::json!({ "auto_save": "Ten" });
+        assert!(version_2(&mut settings).is_err());
+    }
+
+    #[derive(Deserialize, PartialEq, Debug, Default)]
+    #[serde(default)]
+    struct Legacy {
+        initial_seed: String,
+        lock_initial_seed: bool,
+        bot_color: [u8; 4],
+        bot_head: String,
+        auto_save_schedule: Option<Value>,
+        world: Value,
+        size: u32,
+    }
+
+    #[test]
+    fn test_legacy_ron() {
+        let map = Map::new(crate::Size { w: 2, h: 1 });
+        // As eframe stored the app before versioning, with a field unknown to it
+        let text = format!(
+            r#"(bot_head:NW,bot_color:((0,100,0,255)),lock_initial_seed:true,initial_seed:"abc",is_dark:true,auto_save:Some(Five),map:{},size:"big")"#,
+            ron::to_string(&map).unwrap()
+        );
+        assert_eq!(
+            from_legacy_ron::<Legacy>(&text),
+            Legacy {
+                initial_seed: "abc".to_string(),
+                lock_initial_seed: true,
+                bot_color: [0, 100, 0, 255],
+                bot_head: "NW".to_string(),
+                auto_save_schedule: Some(serde_json::json!({ "interval": { "Seconds": 5 } })),
+                world: serde_json::json!({ "map": map }),
+                size: 0,
+            }
+        );
+        assert_eq!(from_legacy_ron::<Legacy>("(broken"), Legacy::default());
+    }
+
+    #[test]
+    fn test_legacy_ron_keeps_plain_fields() {
+        // `Up` is no direction, so the settings don't read as a whole
+        let text =
+            r#"(bot_head:Up,initial_seed:"abc",auto_save:Some(Five),lock_initial_seed:true)"#;
+        assert_eq!(
+            from_legacy_ron::<Legacy>(text),
+            Legacy {
+                initial_seed: "abc".to_string(),
+                lock_initial_seed: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_fields_are_dropped() {
+        let text = r#"{"version": 1, "settings": {"seed": "a", "size": "big"}}"#;
+        assert_eq!(from_str::<Settings>(text), settings("a", 0));
+        assert_eq!(from_str::<Settings>("not json"), Settings::default());
+        assert_eq!(
+            from_str_with::<Settings>(r#"{"version": 1, "settings": 5}"#, &[rename_seed]),
+            Settings::default()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use crate::config::{ConfigError, Format, SimConfig};
+use crate::consts::field;
+use crate::world::{new_seeded_rand, World};
+use crate::Size;
+use base64::engine::general_purpose;
+use base64::Engine;
+
+const SEED_KEY: &str = "seed";
+const SIZE_KEY: &str = "size";
+const CONFIG_KEY: &str = "config";
+
+/// Simulation parameters carried in the URL fragment of the web build,
+/// e.g. `#seed=<base64url>&size=64x48&config=<base64url>`.
+///
+/// Values are encoded as URL-safe base64, so they survive the
+/// percent-decoding which is applied to the fragment by the browser integration.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ShareLink {
+    pub initial_seed: String,
+    /// Size of the map in cells
+    pub map_size: Size<usize>,
+    pub sim_config: Option<SimConfig>,
+}
+
+impl ShareLink {
+    /// World of the link, generated like a new world of the app.
+    /// `fallback` rules are used if the link has none.
+    pub fn generate_world(&self, fallback: &SimConfig) -> World {
+        World::generate(
+            self.map_size,
+            self.sim_config.as_ref().unwrap_or(fallback),
+            new_seeded_rand(&self.initial_seed),
+        )
+    }
+
+    pub fn to_fragment(&self) -> String {
+        let mut fragment = format!(
+            "#{SEED_KEY}={}&{SIZE_KEY}={}x{}",
+            encode_value(&self.initial_seed),
+            self.map_size.w,
+            self.map_size.h
+        );
+        if let Some(sim_config) = &self.sim_config {
+            fragment.push_str(&format!(
+                "&{CONFIG_KEY}={}",
+                encode_value(&Format::Json.export(sim_config))
+            ));
+        }
+        fragment
+    }
+
+    /// Parses fragment with or without leading `#`, unknown keys are ignored.
+    /// Returns `None` without a valid seed or with an invalid size, missing size is
+    /// the default field size. Invalid config is left out of the link and returned
+    /// as the error, so the seed is still used.
+    pub fn from_fragment(fragment: &str) -> Option<(Self, Option<ConfigError>)> {
+        let fragment = fragment.strip_prefix('#').unwrap_or(fragment);
+        let mut initial_seed = None;
+        let mut map_size = Size {
+            w: field::WIDTH,
+            h: field::HEIGHT,
+        };
+        let mut sim_config = None;
+        let mut error = None;
+        for (key, value) in fragment.split('&').filter_map(|pair| pair.split_once('=')) {
+            match key {
+                SEED_KEY => initial_seed = Some(decode_value(value)?),
+                SIZE_KEY => map_size = parse_size(value)?,
+                CONFIG_KEY => {
+                    let config = decode_value(value)
+                        .ok_or_else(|| ConfigError::Parse("config is not base64url".to_string()))
+                        .and_then(|text| Format::Json.import(&text));
+                    match config {
+                        Ok(config) => sim_config = Some(config),
+                        Err(e) => error = Some(e),
+                    }
+                }
+                _ => {}
+            }
+        }
+        let link = Self {
+            initial_seed: initial_seed?,
+            map_size,
+            sim_config,
+        };
+        Some((link, error))
+    }
+}
+
+/// `<w>x<h>` with both sides in `1..=field::MAX_SIDE`
+fn parse_size(value: &str) -> Option<Size<usize>> {
+    let (w, h) = value.split_once('x')?;
+    let (w, h) = (w.parse().ok()?, h.parse().ok()?);
+    let sides = 1..=field::MAX_SIDE;
+    (sides.contains(&w) && sides.contains(&h)).then_some(Size { w, h })
+}
+
+fn encode_value(value: &str) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(value)
+}
+
+fn decode_value(value: &str) -> Option<String> {
+    let bytes = general_purpose::URL_SAFE_NO_PAD.decode(value).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Preset;
+
+    fn default_size() -> Size<usize> {
+        Size {
+            w: field::WIDTH,
+            h: field::HEIGHT,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let link = ShareLink {
+            initial_seed: "a+b/c&d=e #f".to_string(),
+            map_size: Size { w: 3, h: 5 },
+            sim_config: None,
+        };
+        assert_eq!(
+            ShareLink::from_fragment(&link.to_fragment()),
+            Some((link, None))
+        );
+        let link = ShareLink {
+            initial_seed: "seed".to_string(),
+            map_size: default_size(),
+            sim_config: Some(Preset::OceanWorld.config()),
+        };
+        assert_eq!(
+            ShareLink::from_fragment(&link.to_fragment()),
+            Some((link, None))
+        );
+    }
+
+    #[test]
+    fn test_from_fragment() {
+        assert_eq!(ShareLink::from_fragment(""), None);
+        assert_eq!(ShareLink::from_fragment("#other=1"), None);
+        assert_eq!(ShareLink::from_fragment("#seed=!!!"), None);
+        assert_eq!(ShareLink::from_fragment("#seed=c2VlZA&size=0x5"), None);
+        assert_eq!(ShareLink::from_fragment("#seed=c2VlZA&size=5x99999"), None);
+        assert_eq!(ShareLink::from_fragment("#seed=c2VlZA&size=5"), None);
+        assert_eq!(
+            ShareLink::from_fragment("other=1&seed=c2VlZA"),
+            Some((
+                ShareLink {
+                    initial_seed: "seed".to_string(),
+                    map_size: default_size(),
+                    sim_config: None,
+                },
+                None
+            ))
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_keeps_seed() {
+        let seed = encode_value("seed");
+        let link = ShareLink {
+            initial_seed: "seed".to_string(),
+            map_size: default_size(),
+            sim_config: None,
+        };
+        let config = encode_value(r#"{"mutation_rate": 2.0}"#);
+        assert!(matches!(
+            ShareLink::from_fragment(&format!("#seed={seed}&config={config}")),
+            Some((ref parsed, Some(ConfigError::Invalid(_)))) if *parsed == link
+        ));
+        assert!(matches!(
+            ShareLink::from_fragment(&format!("#config=!!!&seed={seed}")),
+            Some((ref parsed, Some(ConfigError::Parse(_)))) if *parsed == link
+        ));
+    }
+
+    #[test]
+    fn test_link_makes_same_world() {
+        let link = ShareLink {
+            initial_seed: "seed".to_string(),
+            map_size: Size { w: 12, h: 7 },
+            sim_config: Some(Preset::OceanWorld.config()),
+        };
+        let (parsed, _) = ShareLink::from_fragment(&link.to_fragment()).unwrap();
+        let world = parsed.generate_world(&SimConfig::default());
+        assert_eq!(world, link.generate_world(&SimConfig::default()));
+        assert_eq!(world.map().size(), link.map_size);
+        assert_eq!(
+            world,
+            World::generate(
+                link.map_size,
+                &Preset::OceanWorld.config(),
+                new_seeded_rand("seed")
+            )
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+
+/// Snapshots of a deterministic simulation taken every `interval` ticks,
+/// the oldest ones are dropped over the capacity.
+///
+/// Any tick after the oldest checkpoint is reconstructed by stepping forward
+/// from the nearest checkpoint, so a snapshot must include the random generator state.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Timeline<S> {
+    interval: u64,
+    capacity: usize,
+    checkpoints: VecDeque<(u64, S)>,
+    /// Latest recorded tick, the future after it is unknown
+    end: u64,
+}
+
+impl<S: Clone> Timeline<S> {
+    pub fn new(interval: u64, capacity: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            capacity,
+            checkpoints: VecDeque::new(),
+            end: 0,
+        }
+    }
+
+    /// Keeps the snapshot if `tick` is a checkpoint tick not recorded yet.
+    /// Returns `true` if kept.
+    pub fn record(&mut self, tick: u64, state: &S) -> bool {
+        self.end = self.end.max(tick);
+        let is_new = self
+            .checkpoints
+            .back()
+            .map_or(true, |&(last, _)| tick > last);
+        if tick % self.interval != 0 || !is_new || self.capacity == 0 {
+            return false;
+        }
+        if self.checkpoints.len() == self.capacity {
+            self.checkpoints.pop_front();
+        }
+        self.checkpoints.push_back((tick, state.clone()));
+        true
+    }
+
+    /// Keeps the snapshot of a state changed at `tick` regardless of the interval,
+    /// forgetting the checkpoints from `tick` on, which hold the unchanged state.
+    pub fn replace(&mut self, tick: u64, state: &S) {
+        self.truncate_after(tick);
+        self.end = tick;
+        if self
+            .checkpoints
+            .back()
+            .is_some_and(|&(last, _)| last == tick)
+        {
+            self.checkpoints.pop_back();
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        if self.checkpoints.len() == self.capacity {
+            self.checkpoints.pop_front();
+        }
+        self.checkpoints.push_back((tick, state.clone()));
+    }
+
+    /// Forgets all checkpoints and keeps the snapshot at `tick`, e.g. when the ticks
+    /// before it can't be stepped through the same way again
+    pub fn restart(&mut self, tick: u64, state: &S) {
+        self.checkpoints.clear();
+        self.replace(tick, state);
+    }
+
+    /// Forgets checkpoints after `tick`, e.g. when the past was changed after a rewind
+    pub fn truncate_after(&mut self, tick: u64) {
+        self.end = self.end.min(tick);
+        while self
+            .checkpoints
+            .back()
+            .is_some_and(|&(last, _)| last > tick)
+        {
+            self.checkpoints.pop_back();
+        }
+    }
+
+    /// Earliest tick which can be reconstructed
+    pub fn first_tick(&self) -> Option<u64> {
+        self.checkpoints.front().map(|&(tick, _)| tick)
+    }
+
+    /// Latest tick which can be reconstructed
+    pub fn last_tick(&self) -> Option<u64> {
+        self.first_tick().map(|_| self.end)
+    }
+
+    /// Latest checkpoint at or before `tick`
+    pub fn nearest(&self, tick: u64) -> Option<(u64, &S)> {
+        self.checkpoints
+            .iter()
+            .rev()
+            .find(|&&(checkpoint, _)| checkpoint <= tick)
+            .map(|(checkpoint, state)| (*checkpoint, state))
+    }
+
+    /// Reconstructs the state at `tick` by calling `step` from the nearest checkpoint.
+    /// Returns `None` if `tick` is before the first checkpoint.
+    pub fn rewind(&self, tick: u64, mut step: impl FnMut(&mut S)) -> Option<S> {
+        let (checkpoint, state) = self.nearest(tick)?;
+        let mut state = state.clone();
+        for _ in checkpoint..tick {
+            step(&mut state);
+        }
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// State is the tick itself
+    fn timeline(ticks: u64, interval: u64, capacity: usize) -> Timeline<u64> {
+        let mut timeline = Timeline::new(interval, capacity);
+        for tick in 0..ticks {
+            timeline.record(tick, &tick);
+        }
+        timeline
+    }
+
+    #[test]
+    fn test_record() {
+        let mut timeline = timeline(100, 10, 3);
+        assert_eq!(timeline.first_tick(), Some(70));
+        assert_eq!(timeline.last_tick(), Some(99));
+        assert!(!timeline.record(90, &90));
+        assert!(!timeline.record(101, &101));
+        assert!(timeline.record(110, &110));
+        assert_eq!(timeline.first_tick(), Some(80));
+    }
+
+    #[test]
+    fn test_rewind() {
+        let timeline = timeline(100, 10, 3);
+        assert_eq!(timeline.nearest(85), Some((80, &80)));
+        let mut steps = 0;
+        let state = timeline.rewind(85, |state| {
+            *state += 1;
+            steps += 1;
+        });
+        assert_eq!((state, steps), (Some(85), 5));
+        assert_eq!(timeline.rewind(69, |_| {}), None);
+        assert_eq!(timeline.rewind(120, |state| *state += 1), Some(120));
+    }
+
+    #[test]
+    fn test_truncate_after() {
+        let mut timeline = timeline(100, 10, 3);
+        timeline.truncate_after(85);
+        assert_eq!(timeline.nearest(99), Some((80, &80)));
+        assert_eq!(timeline.last_tick(), Some(85));
+        assert!(timeline.record(90, &90));
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut timeline = timeline(100, 10, 3);
+        timeline.replace(85, &0);
+        assert_eq!(timeline.nearest(99), Some((85, &0)));
+        assert_eq!(timeline.last_tick(), Some(85));
+        timeline.replace(85, &1);
+        assert_eq!(timeline.nearest(99), Some((85, &1)));
+        assert_eq!(timeline.first_tick(), Some(70));
+        timeline.replace(80, &2);
+        assert_eq!(timeline.nearest(99), Some((80, &2)));
+        assert!(timeline.record(90, &90));
+        timeline.replace(95, &3);
+        assert_eq!(timeline.first_tick(), Some(80));
+    }
+
+    #[test]
+    fn test_restart() {
+        let mut timeline = timeline(100, 10, 3);
+        timeline.restart(85, &0);
+        assert_eq!(timeline.first_tick(), Some(85));
+        assert_eq!(timeline.last_tick(), Some(85));
+        assert_eq!(timeline.rewind(80, |_| {}), None);
+        assert_eq!(timeline.rewind(87, |state| *state += 1), Some(2));
+    }
+}
@@ -0,0 +1,1150 @@
+use crate::bot::Direction;
+use crate::camera::Lineage;
+use crate::colony::{colonies, share_energy, Links};
+use crate::config::SimConfig;
+use crate::genome::{is_kin, Genome, Seen, Senses, Transfer, TransferCounter};
+use crate::map::{Cell, Map, Object, Terrain};
+use crate::{Point, Size};
+use egui::Color32;
+use rand::{Fill, Rng, SeedableRng};
+use rand_seeder::SipHasher;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+
+/// Bots are numbered in the order of their birth.
+pub type BotId = u64;
+
+/// Living bot, drawn on the map as [`Object::Bot`] in its cell.
+#[derive(Deserialize, Serialize, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Bot {
+    pub parent: Option<BotId>,
+    /// Offspring, living or dead with living descendants, oldest first
+    pub children: Vec<BotId>,
+    pub position: Point<usize>,
+    pub genome: Genome,
+    pub energy: u32,
+    /// Ticks lived
+    pub age: u32,
+}
+
+/// Dead bot kept while it has living descendants, so its lineage can be followed.
+#[derive(Deserialize, Serialize, Clone, Eq, PartialEq, Hash, Debug)]
+struct Ancestor {
+    parent: Option<BotId>,
+    children: Vec<BotId>,
+}
+
+/// What a gene which is neither a sensing nor a transfer command makes the bot do.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+enum Action {
+    /// Turns clockwise by the number of 45 degree steps
+    Rotate(u32),
+    /// Moves into the neighbour cell in the direction relative to the facing one,
+    /// stepping through mud costs twice as much
+    Step(u32),
+    /// Eats an apple or organics in the neighbour cell in the relative direction
+    Eat(u32),
+    /// Gains energy from the light of the cell
+    Photosynthesis,
+    /// Gives energy to an offspring in a free neighbour cell
+    Reproduce,
+    /// Moves the genome pointer forward by the gene value without acting
+    Jump(u8),
+}
+
+impl From<u8> for Action {
+    fn from(gene: u8) -> Self {
+        let direction = gene as u32 & 0b111;
+        match gene {
+            0..=7 => Action::Rotate(direction),
+            8..=15 => Action::Step(direction),
+            16..=23 => Action::Eat(direction),
+            24..=27 => Action::Photosynthesis,
+            28..=31 => Action::Reproduce,
+            _ => Action::Jump(gene),
+        }
+    }
+}
+
+/// Map with the bots living on it, simulated tick by tick.
+///
+/// The world is deterministic: the same world and rules give the same next tick,
+/// since the random generator is a part of the world.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(try_from = "RawWorld")]
+pub struct World {
+    map: Map,
+    bots: BTreeMap<BotId, Bot>,
+    ancestors: BTreeMap<BotId, Ancestor>,
+    tick: u64,
+    next_id: BotId,
+    rng: Xoshiro256PlusPlus,
+    /// Genome transfers during the last tick
+    transfers: TransferCounter,
+    /// Bot in each occupied cell, rebuilt from `bots` on load
+    #[serde(skip)]
+    occupants: HashMap<Point<usize>, BotId>,
+}
+
+/// Deserialized world before its bots are checked against the map.
+/// Missing fields are defaults, so a bare map makes an empty world.
+#[derive(Deserialize)]
+#[serde(default)]
+struct RawWorld {
+    map: Map,
+    bots: BTreeMap<BotId, Bot>,
+    ancestors: BTreeMap<BotId, Ancestor>,
+    tick: u64,
+    next_id: BotId,
+    rng: Xoshiro256PlusPlus,
+    transfers: TransferCounter,
+}
+
+impl Default for RawWorld {
+    fn default() -> Self {
+        Self {
+            map: Map::default(),
+            bots: BTreeMap::new(),
+            ancestors: BTreeMap::new(),
+            tick: 0,
+            next_id: 0,
+            rng: Xoshiro256PlusPlus::seed_from_u64(0),
+            transfers: TransferCounter::default(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct InvalidBot(pub BotId);
+
+impl Display for InvalidBot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Bot {} is not in a bot cell of its own", self.0)
+    }
+}
+
+impl std::error::Error for InvalidBot {}
+
+impl TryFrom<RawWorld> for World {
+    type Error = InvalidBot;
+
+    fn try_from(raw: RawWorld) -> Result<Self, Self::Error> {
+        let mut occupants = HashMap::new();
+        for (&id, bot) in &raw.bots {
+            let is_bot_cell = matches!(
+                raw.map.get(bot.position),
+                Some(Cell {
+                    object: Some(Object::Bot { .. }),
+                    ..
+                })
+            );
+            if !is_bot_cell || id >= raw.next_id || occupants.insert(bot.position, id).is_some() {
+                return Err(InvalidBot(id));
+            }
+        }
+        Ok(Self {
+            map: raw.map,
+            bots: raw.bots,
+            ancestors: raw.ancestors,
+            tick: raw.tick,
+            next_id: raw.next_id,
+            rng: raw.rng,
+            transfers: raw.transfers,
+            occupants,
+        })
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new(Map::default(), Xoshiro256PlusPlus::seed_from_u64(0))
+    }
+}
+
+impl World {
+    /// World without bots, bots drawn on the map come alive on the next [`World::edit`]
+    /// or [`World::step`]
+    pub fn new(map: Map, rng: Xoshiro256PlusPlus) -> Self {
+        Self {
+            map,
+            bots: BTreeMap::new(),
+            ancestors: BTreeMap::new(),
+            tick: 0,
+            next_id: 0,
+            rng,
+            transfers: TransferCounter::default(),
+            occupants: HashMap::new(),
+        }
+    }
+
+    /// Random terrain with bots of random genomes on land, as the rules say
+    pub fn generate(size: Size<usize>, config: &SimConfig, rng: Xoshiro256PlusPlus) -> Self {
+        let mut world = Self::new(Map::new(size), rng);
+        let points: Vec<_> = world.map.rect().points().collect();
+        for &point in &points {
+            let roll: f32 = world.rng.gen();
+            let terrain = if roll < config.terrain.ocean {
+                Terrain::Ocean
+            } else if roll < config.terrain.ocean + config.terrain.mud {
+                Terrain::Mud
+            } else {
+                Terrain::Land
+            };
+            world.map.set(
+                point,
+                Cell {
+                    terrain,
+                    object: None,
+                },
+            );
+        }
+        let mut land: Vec<_> = points
+            .into_iter()
+            .filter(|&point| world.is_free(point))
+            .collect();
+        for _ in 0..config.spawn.initial_bots {
+            if land.is_empty() {
+                break;
+            }
+            let position = land.swap_remove(world.rng.gen_range(0..land.len()));
+            let genome = Genome::random(&mut world.rng);
+            let head = Direction::ALL[world.rng.gen_range(0..Direction::ALL.len())];
+            let color = Color32::from_rgb(world.rng.gen(), world.rng.gen(), world.rng.gen());
+            world.spawn(position, genome, config.energy.max / 2, head, color);
+        }
+        world
+    }
+
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    /// Ticks simulated since the world was created
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Genome transfers during the last tick
+    pub fn transfers(&self) -> TransferCounter {
+        self.transfers
+    }
+
+    pub fn population(&self) -> usize {
+        self.bots.len()
+    }
+
+    pub fn bot(&self, id: BotId) -> Option<&Bot> {
+        self.bots.get(&id)
+    }
+
+    pub fn bot_at(&self, point: Point<usize>) -> Option<BotId> {
+        self.occupants.get(&point).copied()
+    }
+
+    /// Changes the map. Bots drawn by `f` come alive with random genomes and half of
+    /// the maximum energy, bots whose cells are erased or overwritten disappear.
+    pub fn edit<R>(&mut self, config: &SimConfig, f: impl FnOnce(&mut Map) -> R) -> R {
+        let result = f(&mut self.map);
+        self.sync(config);
+        result
+    }
+
+    /// Puts a new bot into an empty cell, returns `None` if the cell is taken
+    pub fn spawn(
+        &mut self,
+        position: Point<usize>,
+        genome: Genome,
+        energy: u32,
+        head: Direction,
+        color: Color32,
+    ) -> Option<BotId> {
+        if self.map.get(position)?.object.is_some() {
+            return None;
+        }
+        let id = self.add_bot(None, position, genome, energy);
+        self.map.get_mut(position)?.object = Some(Object::Bot {
+            head,
+            color,
+            links: Links::default(),
+        });
+        Some(id)
+    }
+
+    /// Simulates one tick: every bot living at its start acts once in the order of birth,
+    /// linked bots share energy, colonies with a starved or too old bot die, then apples grow.
+    pub fn step(&mut self, config: &SimConfig) {
+        self.sync(config);
+        self.tick += 1;
+        self.transfers = TransferCounter::default();
+        let ids: Vec<_> = self.bots.keys().copied().collect();
+        for id in ids {
+            // Taken out while acting, so the senses can borrow the rest of the world
+            let Some(mut bot) = self.bots.remove(&id) else {
+                continue;
+            };
+            self.act(id, &mut bot, config);
+            bot.age = bot.age.saturating_add(1);
+            self.bots.insert(id, bot);
+        }
+
+        let ids: Vec<_> = self.bots.keys().copied().collect();
+        let links = self.link_pairs(&ids);
+        let mut energy: Vec<_> = self.bots.values().map(|bot| bot.energy).collect();
+        share_energy(&mut energy, &links, config.colony.share_rate);
+        for (bot, energy) in self.bots.values_mut().zip(energy) {
+            bot.energy = energy;
+        }
+        let colony = colonies(ids.len(), &links);
+        let mut is_dead = vec![false; ids.len()];
+        for (i, bot) in self.bots.values().enumerate() {
+            if bot.energy == 0 || bot.age > config.lifespan {
+                is_dead[colony[i]] = true;
+            }
+        }
+        for (i, id) in ids.into_iter().enumerate() {
+            if is_dead[colony[i]] {
+                if let Some(bot) = self.bots.remove(&id) {
+                    self.die(id, bot);
+                }
+            }
+        }
+        self.grow_apples(config);
+    }
+
+    /// Links between the bots as pairs of indices into `ids`, each link once
+    fn link_pairs(&self, ids: &[BotId]) -> Vec<(usize, usize)> {
+        let index: HashMap<_, _> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let mut pairs = Vec::new();
+        for (i, id) in ids.iter().enumerate() {
+            let Some(bot) = self.bots.get(id) else {
+                continue;
+            };
+            for direction in self.links(bot.position).iter() {
+                let other = self
+                    .neighbour(bot.position, direction)
+                    .and_then(|point| self.bot_at(point))
+                    .and_then(|other| index.get(&other));
+                if let Some(&j) = other.filter(|&&j| i < j) {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn act(&mut self, id: BotId, bot: &mut Bot, config: &SimConfig) {
+        let head = self.head(bot.position);
+        let own = bot.genome.clone();
+        let view = View {
+            world: self,
+            config,
+            position: bot.position,
+            genome: &own,
+            energy: bot.energy,
+            age: bot.age,
+        };
+        // Too many sensing commands in a row waste the tick
+        let Some(gene) = bot.genome.sense(head, &view) else {
+            return;
+        };
+        let energy = &config.energy;
+        if let Ok(transfer) = Transfer::try_from(gene) {
+            bot.energy = bot.energy.saturating_sub(energy.transfer);
+            let other = self
+                .neighbour(bot.position, head)
+                .and_then(|point| self.bot_at(point))
+                .and_then(|other| self.bots.get_mut(&other));
+            match other {
+                Some(other) => {
+                    bot.genome.transfer(&mut other.genome);
+                    self.transfers.record(transfer);
+                }
+                // Nobody to transfer to, the parameter is skipped
+                None => bot.genome.skip(2),
+            }
+            return;
+        }
+        let action = Action::from(gene);
+        match action {
+            Action::Rotate(n) => {
+                bot.energy = bot.energy.saturating_sub(energy.rotate);
+                self.set_head(bot.position, head.rotate_cw(n));
+            }
+            Action::Step(n) => {
+                let cost = match self.map.get(bot.position) {
+                    Some(cell) if cell.terrain == Terrain::Mud => energy.step.saturating_mul(2),
+                    _ => energy.step,
+                };
+                bot.energy = bot.energy.saturating_sub(cost);
+                self.move_colony(id, bot, head.rotate_cw(n));
+            }
+            Action::Eat(n) => {
+                let target = self.neighbour(bot.position, head.rotate_cw(n));
+                if let Some(cell) = target.and_then(|target| self.map.get_mut(target)) {
+                    let gain = match cell.object {
+                        Some(Object::Apple) => energy.apple,
+                        Some(Object::Organics) => energy.organics,
+                        _ => 0,
+                    };
+                    if gain > 0 {
+                        cell.object = None;
+                        bot.energy = bot.energy.saturating_add(gain);
+                    }
+                }
+            }
+            Action::Photosynthesis => {
+                let light = light(&self.map, bot.position, config);
+                bot.energy = bot
+                    .energy
+                    .saturating_add((energy.photosynthesis as f32 * light).round() as u32);
+            }
+            Action::Reproduce => self.reproduce(id, bot, head, config),
+            Action::Jump(_) => {}
+        }
+        bot.energy = bot.energy.min(energy.max);
+        bot.genome.skip(match action {
+            Action::Jump(gene) => gene as usize,
+            _ => 1,
+        });
+    }
+
+    fn reproduce(&mut self, id: BotId, bot: &mut Bot, head: Direction, config: &SimConfig) {
+        let cost = config.energy.reproduce;
+        if bot.energy <= cost {
+            return;
+        }
+        // Offspring goes behind the parent, or to the nearest free cell clockwise from there
+        let behind = head.opposite();
+        let Some((direction, position)) = (0..8).find_map(|n| {
+            let direction = behind.rotate_cw(n);
+            let position = self.neighbour(bot.position, direction)?;
+            self.is_free(position).then_some((direction, position))
+        }) else {
+            return;
+        };
+        bot.energy -= cost;
+        let color = match self.map.get(bot.position) {
+            Some(Cell {
+                object: Some(Object::Bot { color, .. }),
+                ..
+            }) => *color,
+            _ => Color32::WHITE,
+        };
+        let genome = bot.genome.offspring(&mut self.rng, config.mutation_rate);
+        let child = self.add_bot(Some(id), position, genome, cost);
+        bot.children.push(child);
+        let mut links = Links::default();
+        if config.colony.link_offspring {
+            links.insert(direction.opposite());
+            if let Some(parent_links) = self.links_mut(bot.position) {
+                parent_links.insert(direction);
+            }
+        }
+        if let Some(cell) = self.map.get_mut(position) {
+            cell.object = Some(Object::Bot {
+                head: direction,
+                color,
+                links,
+            });
+        }
+    }
+
+    fn add_bot(
+        &mut self,
+        parent: Option<BotId>,
+        position: Point<usize>,
+        genome: Genome,
+        energy: u32,
+    ) -> BotId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.bots.insert(
+            id,
+            Bot {
+                parent,
+                children: Vec::new(),
+                position,
+                genome,
+                energy,
+                age: 0,
+            },
+        );
+        self.occupants.insert(position, id);
+        id
+    }
+
+    /// Moves the colony of the acting bot by one cell if every member has a free cell to go to
+    fn move_colony(&mut self, id: BotId, bot: &mut Bot, direction: Direction) {
+        let members = self.colony(bot.position);
+        let is_member: HashSet<_> = members.iter().copied().collect();
+        let targets: Option<Vec<_>> = members
+            .iter()
+            .map(|&member| {
+                self.neighbour(member, direction)
+                    .filter(|target| is_member.contains(target) || self.is_free(*target))
+            })
+            .collect();
+        let Some(targets) = targets else {
+            return;
+        };
+        let moved: Vec<_> = members
+            .iter()
+            .map(|member| {
+                let object = self
+                    .map
+                    .get_mut(*member)
+                    .and_then(|cell| cell.object.take());
+                (object, self.occupants.remove(member))
+            })
+            .collect();
+        for (target, (object, occupant)) in targets.into_iter().zip(moved) {
+            if let Some(cell) = self.map.get_mut(target) {
+                cell.object = object;
+            }
+            let Some(occupant) = occupant else {
+                continue;
+            };
+            self.occupants.insert(target, occupant);
+            if occupant == id {
+                bot.position = target;
+            } else if let Some(member) = self.bots.get_mut(&occupant) {
+                member.position = target;
+            }
+        }
+    }
+
+    /// Cells of the bots linked with the bot at `position`, directly or through others
+    fn colony(&self, position: Point<usize>) -> Vec<Point<usize>> {
+        let mut members = vec![position];
+        let mut seen = HashSet::from([position]);
+        let mut i = 0;
+        while let Some(&member) = members.get(i) {
+            for direction in self.links(member).iter() {
+                if let Some(neighbour) = self.neighbour(member, direction) {
+                    if seen.insert(neighbour) {
+                        members.push(neighbour);
+                    }
+                }
+            }
+            i += 1;
+        }
+        members
+    }
+
+    /// Leaves organics in the cell of the bot
+    fn die(&mut self, id: BotId, bot: Bot) {
+        self.occupants.remove(&bot.position);
+        if let Some(cell) = self.map.get_mut(bot.position) {
+            cell.object = Some(Object::Organics);
+        }
+        self.forget(id, bot.parent, bot.children);
+    }
+
+    /// Keeps a dead bot in the lineage while it has descendants, otherwise
+    /// removes it from its parent and forgets the ancestors left without descendants
+    fn forget(&mut self, id: BotId, parent: Option<BotId>, children: Vec<BotId>) {
+        if !children.is_empty() {
+            self.ancestors.insert(id, Ancestor { parent, children });
+            return;
+        }
+        let Some(parent) = parent else {
+            return;
+        };
+        if let Some(bot) = self.bots.get_mut(&parent) {
+            bot.children.retain(|&child| child != id);
+        } else if let Some(mut ancestor) = self.ancestors.remove(&parent) {
+            ancestor.children.retain(|&child| child != id);
+            self.forget(parent, ancestor.parent, ancestor.children);
+        }
+    }
+
+    /// Adopts bots drawn on the map, forgets bots whose cells were changed
+    /// and removes links which are not mutual
+    fn sync(&mut self, config: &SimConfig) {
+        let gone: Vec<_> = self
+            .bots
+            .iter()
+            .filter(|(_, bot)| {
+                !matches!(
+                    self.map.get(bot.position),
+                    Some(Cell {
+                        object: Some(Object::Bot { .. }),
+                        ..
+                    })
+                )
+            })
+            .map(|(&id, _)| id)
+            .collect();
+        for id in gone {
+            if let Some(bot) = self.bots.remove(&id) {
+                self.occupants.remove(&bot.position);
+                self.forget(id, bot.parent, bot.children);
+            }
+        }
+        let drawn: Vec<_> = self
+            .map
+            .iter()
+            .filter(|(point, cell)| {
+                matches!(cell.object, Some(Object::Bot { .. }))
+                    && !self.occupants.contains_key(point)
+            })
+            .map(|(point, _)| point)
+            .collect();
+        for position in drawn {
+            let genome = Genome::random(&mut self.rng);
+            self.add_bot(None, position, genome, config.energy.max / 2);
+        }
+        let mut broken = Vec::new();
+        for bot in self.bots.values() {
+            for direction in self.links(bot.position).iter() {
+                let is_mutual = self
+                    .neighbour(bot.position, direction)
+                    .is_some_and(|neighbour| self.links(neighbour).contains(direction.opposite()));
+                if !is_mutual {
+                    broken.push((bot.position, direction));
+                }
+            }
+        }
+        for (point, direction) in broken {
+            if let Some(links) = self.links_mut(point) {
+                links.remove(direction);
+            }
+        }
+    }
+
+    /// Links of the bot in the cell, none if there is no bot
+    fn links(&self, position: Point<usize>) -> Links {
+        match self.map.get(position) {
+            Some(Cell {
+                object: Some(Object::Bot { links, .. }),
+                ..
+            }) => *links,
+            _ => Links::default(),
+        }
+    }
+
+    fn links_mut(&mut self, position: Point<usize>) -> Option<&mut Links> {
+        match self.map.get_mut(position) {
+            Some(Cell {
+                object: Some(Object::Bot { links, .. }),
+                ..
+            }) => Some(links),
+            _ => None,
+        }
+    }
+
+    fn grow_apples(&mut self, config: &SimConfig) {
+        let points: Vec<_> = self.map.rect().points().collect();
+        for point in points {
+            if self.is_free(point) && self.rng.gen::<f32>() < config.spawn.apple {
+                if let Some(cell) = self.map.get_mut(point) {
+                    cell.object = Some(Object::Apple);
+                }
+            }
+        }
+    }
+
+    /// Whether a bot can step into the cell
+    fn is_free(&self, point: Point<usize>) -> bool {
+        self.map
+            .get(point)
+            .is_some_and(|cell| cell.object.is_none() && !cell.terrain.is_water())
+    }
+
+    fn neighbour(&self, point: Point<usize>, direction: Direction) -> Option<Point<usize>> {
+        let point = Point::<i32>::try_from(point).ok()? + Point::from(direction);
+        Point::try_from(point)
+            .ok()
+            .filter(|&point| self.map.rect().is_in_bounds(point))
+    }
+
+    fn head(&self, position: Point<usize>) -> Direction {
+        match self.map.get(position) {
+            Some(Cell {
+                object: Some(Object::Bot { head, .. }),
+                ..
+            }) => *head,
+            _ => Direction::default(),
+        }
+    }
+
+    fn set_head(&mut self, position: Point<usize>, direction: Direction) {
+        if let Some(Cell {
+            object: Some(Object::Bot { head, .. }),
+            ..
+        }) = self.map.get_mut(position)
+        {
+            *head = direction;
+        }
+    }
+}
+
+impl Lineage for World {
+    type Id = BotId;
+
+    fn position(&self, id: BotId) -> Option<Point<usize>> {
+        self.bot(id).map(|bot| bot.position)
+    }
+
+    fn children(&self, id: BotId) -> Vec<BotId> {
+        match (self.bots.get(&id), self.ancestors.get(&id)) {
+            (Some(bot), _) => bot.children.clone(),
+            (None, Some(ancestor)) => ancestor.children.clone(),
+            (None, None) => Vec::new(),
+        }
+    }
+}
+
+/// Part of the full light reaching the cell: it fades with depth, water absorbs a part
+fn light(map: &Map, point: Point<usize>, config: &SimConfig) -> f32 {
+    let light = 1.0 - depth(map, point);
+    match map.get(point) {
+        Some(cell) if cell.terrain.is_water() => light * (1.0 - config.terrain.light_absorption),
+        _ => light,
+    }
+}
+
+/// Row of the cell as a part of the map height, the top row is 0 and the bottom one is 1
+fn depth(map: &Map, point: Point<usize>) -> f32 {
+    match map.size().h {
+        0 | 1 => 0.0,
+        h => point.y as f32 / (h - 1) as f32,
+    }
+}
+
+/// Random generator seeded by the hash of `h`, so the same seed makes the same world
+pub(crate) fn new_seeded_rand<H, R>(h: H) -> R
+where
+    H: Hash,
+    R: SeedableRng,
+    R::Seed: Fill,
+{
+    let hasher = SipHasher::from(h);
+    let mut hasher_rng = hasher.into_rng();
+    let mut seed = R::Seed::default();
+    hasher_rng.fill(&mut seed);
+    R::from_seed(seed)
+}
+
+/// Senses of the bot acting at `position`.
+struct View<'a> {
+    world: &'a World,
+    config: &'a SimConfig,
+    position: Point<usize>,
+    /// Own genome as it was before the tick
+    genome: &'a Genome,
+    energy: u32,
+    age: u32,
+}
+
+impl Senses for View<'_> {
+    fn look(&self, direction: Direction) -> Seen {
+        // The edge of the map is a wall
+        let Some(cell) = self
+            .world
+            .neighbour(self.position, direction)
+            .and_then(|point| self.world.map.get(point))
+        else {
+            return Seen::Rock;
+        };
+        match cell.object {
+            Some(Object::Rock) => Seen::Rock,
+            Some(Object::Apple) => Seen::Apple,
+            Some(Object::Organics) => Seen::Organics,
+            Some(Object::Bot { .. }) => match self.genome_at(direction) {
+                Some(other) if is_kin(self.genome, other, self.kin_threshold()) => Seen::Relative,
+                _ => Seen::Foreign,
+            },
+            None if cell.terrain.is_water() => Seen::Water,
+            None => Seen::Empty,
+        }
+    }
+
+    fn genome_at(&self, direction: Direction) -> Option<&Genome> {
+        let point = self.world.neighbour(self.position, direction)?;
+        let id = self.world.bot_at(point)?;
+        self.world.bot(id).map(|bot| &bot.genome)
+    }
+
+    fn kin_threshold(&self) -> usize {
+        self.config.kin_threshold as usize
+    }
+
+    fn energy(&self) -> f32 {
+        self.energy as f32 / self.config.energy.max.max(1) as f32
+    }
+
+    fn age(&self) -> f32 {
+        self.age as f32 / self.config.lifespan.max(1) as f32
+    }
+
+    fn depth(&self) -> f32 {
+        depth(&self.world.map, self.position)
+    }
+
+    fn light(&self) -> f32 {
+        light(&self.world.map, self.position, self.config)
+    }
+
+    fn minerals(&self) -> f32 {
+        // Minerals settle at the bottom
+        self.depth()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera;
+    use crate::consts::genome;
+    use crate::genome::{distance, Command};
+    use crate::timeline::Timeline;
+
+    fn world(w: usize, h: usize) -> World {
+        World::new(
+            Map::new(Size { w, h }),
+            Xoshiro256PlusPlus::seed_from_u64(1),
+        )
+    }
+
+    fn config() -> SimConfig {
+        let mut config = SimConfig::default();
+        config.spawn.apple = 0.0;
+        config
+    }
+
+    /// Genome repeating `genes`
+    fn genome(genes: &[u8]) -> Genome {
+        Genome::from(std::array::from_fn(|i| genes[i % genes.len()]))
+    }
+
+    fn spawn(world: &mut World, x: usize, y: usize, genes: &[u8], energy: u32) -> BotId {
+        let head = Direction::E;
+        world
+            .spawn(Point { x, y }, genome(genes), energy, head, Color32::RED)
+            .unwrap()
+    }
+
+    fn object(world: &World, x: usize, y: usize) -> Option<Object> {
+        world.map().get(Point { x, y }).unwrap().object
+    }
+
+    #[test]
+    fn test_sensing_drives_actions() {
+        // Looks ahead: eats an apple at 10, turns around at 12 otherwise
+        let mut genes = [0; genome::SIZE];
+        genes[..2].copy_from_slice(&[Command::Look as u8, 0]);
+        genes[2..2 + Seen::COUNT].fill(12);
+        genes[2 + Seen::Apple as usize] = 10;
+        genes[10] = 16;
+        genes[12] = 4;
+        let mut world = world(3, 1);
+        let config = config();
+        let id = spawn(&mut world, 0, 0, &genes, 100);
+        world.edit(&config, |map| {
+            map.get_mut(Point { x: 1, y: 0 }).unwrap().object = Some(Object::Apple)
+        });
+        world.step(&config);
+        assert_eq!(world.bot(id).unwrap().energy, 100 + config.energy.apple);
+        assert_eq!(object(&world, 1, 0), None);
+        assert_eq!(world.bot(id).unwrap().genome.pointer(), 11);
+        world.bots.get_mut(&id).unwrap().genome = genome(&genes);
+        world.step(&config);
+        assert_eq!(world.head(Point { x: 0, y: 0 }), Direction::W);
+    }
+
+    #[test]
+    fn test_step_and_rotate() {
+        let mut world = world(3, 2);
+        let config = config();
+        // Step ahead, then turn clockwise by 90 degrees
+        let id = spawn(&mut world, 0, 0, &[8, 2], 100);
+        world.step(&config);
+        assert_eq!(world.position(id), Some(Point { x: 1, y: 0 }));
+        assert_eq!(world.bot_at(Point { x: 1, y: 0 }), Some(id));
+        assert_eq!(object(&world, 0, 0), None);
+        world.step(&config);
+        assert_eq!(world.head(Point { x: 1, y: 0 }), Direction::S);
+        world.step(&config);
+        assert_eq!(world.position(id), Some(Point { x: 1, y: 1 }));
+        let energy = 100 - 2 * config.energy.step - config.energy.rotate;
+        assert_eq!(world.bot(id).unwrap().energy, energy);
+    }
+
+    #[test]
+    fn test_death_leaves_organics() {
+        let mut world = world(2, 2);
+        let mut config = config();
+        let starving = spawn(&mut world, 0, 0, &[0], config.energy.rotate);
+        config.lifespan = 2;
+        let old = spawn(&mut world, 1, 1, &[24], 100);
+        world.step(&config);
+        assert_eq!(world.bot(starving), None);
+        assert_eq!(object(&world, 0, 0), Some(Object::Organics));
+        world.step(&config);
+        assert!(world.bot(old).is_some());
+        world.step(&config);
+        assert_eq!(world.population(), 0);
+        assert_eq!(object(&world, 1, 1), Some(Object::Organics));
+    }
+
+    #[test]
+    fn test_reproduce_and_follow() {
+        let mut world = world(3, 1);
+        let config = config();
+        let energy = config.energy.reproduce * 2;
+        let parent = spawn(&mut world, 1, 0, &[28], energy);
+        world.step(&config);
+        let child = world.bot_at(Point { x: 0, y: 0 }).unwrap();
+        assert_eq!(world.bot(child).unwrap().parent, Some(parent));
+        assert_eq!(world.bot(child).unwrap().energy, config.energy.reproduce);
+        assert_eq!(
+            world.bot(parent).unwrap().energy,
+            energy - config.energy.reproduce
+        );
+        assert_eq!(world.children(parent), [child]);
+        // Child went behind the parent and faces away from it
+        assert_eq!(world.head(Point { x: 0, y: 0 }), Direction::W);
+
+        world.edit(&config, |map| {
+            map.get_mut(Point { x: 1, y: 0 }).unwrap().object = None
+        });
+        assert_eq!(
+            camera::follow(&world, parent),
+            Some((child, Point { x: 0, y: 0 }))
+        );
+        world.edit(&config, |map| {
+            map.get_mut(Point { x: 0, y: 0 }).unwrap().object = None
+        });
+        assert_eq!(camera::follow(&world, parent), None);
+        assert!(world.ancestors.is_empty());
+    }
+
+    #[test]
+    fn test_kin_sensing() {
+        // Looks ahead: steps back from relatives at 10, rotates away from others at 11
+        let mut genes = [0; genome::SIZE];
+        genes[..2].copy_from_slice(&[Command::Look as u8, 0]);
+        genes[2..2 + Seen::COUNT].fill(11);
+        genes[2 + Seen::Relative as usize] = 10;
+        genes[10] = 12;
+        genes[11] = 4;
+        let mut world = world(4, 1);
+        let mut config = config();
+        config.kin_threshold = 2;
+        let id = spawn(&mut world, 1, 0, &genes, 100);
+        let mut relative = genes;
+        relative[20] = 1;
+        spawn(&mut world, 2, 0, &relative, 100);
+        world.step(&config);
+        assert_eq!(world.position(id), Some(Point { x: 0, y: 0 }));
+
+        let mut world = self::world(4, 1);
+        let id = spawn(&mut world, 1, 0, &genes, 100);
+        relative[21] = 1;
+        spawn(&mut world, 2, 0, &relative, 100);
+        world.step(&config);
+        assert_eq!(world.position(id), Some(Point { x: 1, y: 0 }));
+        assert_eq!(world.head(Point { x: 1, y: 0 }), Direction::W);
+    }
+
+    #[test]
+    fn test_mutation() {
+        let mut world = world(3, 1);
+        let mut config = config();
+        config.mutation_rate = 1.0;
+        let energy = config.energy.reproduce * 3;
+        let parent = spawn(&mut world, 1, 0, &[28], energy);
+        world.step(&config);
+        world.step(&config);
+        let genomes: Vec<_> = world
+            .children(parent)
+            .into_iter()
+            .map(|child| &world.bot(child).unwrap().genome)
+            .collect();
+        let parent = &world.bot(parent).unwrap().genome;
+        assert_eq!(genomes.len(), 2);
+        assert!(genomes.iter().all(|genome| distance(parent, genome) <= 1));
+        assert!(genomes.iter().any(|genome| distance(parent, genome) == 1));
+    }
+
+    /// Colony of a parent at `(1, 0)` and its linked child at `(0, 0)`
+    fn colony(config: &mut SimConfig, genes: &[u8]) -> (World, BotId, BotId) {
+        let mut world = world(4, 2);
+        config.colony.link_offspring = true;
+        config.colony.share_rate = 0.0;
+        let energy = config.energy.reproduce * 2;
+        let parent = spawn(&mut world, 1, 0, &[28], energy);
+        world.step(config);
+        let child = world.bot_at(Point { x: 0, y: 0 }).unwrap();
+        for id in [parent, child] {
+            world.bots.get_mut(&id).unwrap().genome = genome(genes);
+        }
+        (world, parent, child)
+    }
+
+    #[test]
+    fn test_linked_offspring() {
+        let mut config = config();
+        let (world, parent, child) = colony(&mut config, &[24]);
+        let links = |x, y| world.links(Point { x, y }).iter().collect::<Vec<_>>();
+        assert_eq!(links(1, 0), [Direction::W]);
+        assert_eq!(links(0, 0), [Direction::E]);
+        assert_eq!(world.link_pairs(&[parent, child]), [(0, 1)]);
+    }
+
+    #[test]
+    fn test_colony_moves_as_unit() {
+        let mut config = config();
+        // The parent steps to the east pulling the child, until the rock blocks it
+        let (mut world, parent, child) = colony(&mut config, &[8]);
+        world.bots.get_mut(&child).unwrap().genome = genome(&[24]);
+        world.edit(&config, |map| {
+            map.get_mut(Point { x: 3, y: 0 }).unwrap().object = Some(Object::Rock)
+        });
+        world.step(&config);
+        assert_eq!(world.position(parent), Some(Point { x: 2, y: 0 }));
+        assert_eq!(world.position(child), Some(Point { x: 1, y: 0 }));
+        world.step(&config);
+        assert_eq!(world.position(parent), Some(Point { x: 2, y: 0 }));
+        assert_eq!(world.position(child), Some(Point { x: 1, y: 0 }));
+        assert_eq!(world.links(Point { x: 2, y: 0 }).iter().count(), 1);
+        assert_eq!(object(&world, 0, 0), None);
+    }
+
+    #[test]
+    fn test_colony_shares_energy_and_dies_as_unit() {
+        let mut config = config();
+        let (mut world, parent, child) = colony(&mut config, &[24]);
+        let energy = |world: &World, id| world.bot(id).unwrap().energy;
+        config.colony.share_rate = 1.0;
+        config.energy.photosynthesis = 0;
+        world.step(&config);
+        assert_eq!(energy(&world, parent), energy(&world, child));
+
+        world.bots.get_mut(&child).unwrap().age = config.lifespan;
+        world.step(&config);
+        assert_eq!(world.population(), 0);
+        assert_eq!(object(&world, 0, 0), Some(Object::Organics));
+        assert_eq!(object(&world, 1, 0), Some(Object::Organics));
+    }
+
+    #[test]
+    fn test_links_to_erased_bots_are_removed() {
+        let mut config = config();
+        let (mut world, parent, _) = colony(&mut config, &[24]);
+        world.edit(&config, |map| {
+            map.get_mut(Point { x: 0, y: 0 }).unwrap().object = None
+        });
+        assert!(world.links(Point { x: 1, y: 0 }).is_empty());
+        assert_eq!(world.population(), 1);
+        assert!(world.bot(parent).is_some());
+    }
+
+    #[test]
+    fn test_transfer() {
+        let mut world = world(3, 1);
+        let config = config();
+        let virus = [Transfer::Infect as u8, 2, 24];
+        let id = spawn(&mut world, 0, 0, &virus, 100);
+        let host = spawn(&mut world, 1, 0, &[24], 100);
+        world.step(&config);
+        assert_eq!(world.transfers().infections, 1);
+        let bot = |id| world.bot(id).unwrap();
+        assert_eq!(bot(id).energy, 100 - config.energy.transfer);
+        assert_eq!(bot(id).genome.pointer(), 2);
+        // The host runs the virus gene at once, facing nobody, and pays for it too
+        assert_eq!(bot(host).genome.genes()[..3], virus);
+        assert_eq!(bot(host).energy, 100 - config.energy.transfer);
+        assert_eq!(bot(host).genome.pointer(), 2);
+
+        world.step(&config);
+        assert_eq!(world.transfers(), TransferCounter::default());
+    }
+
+    #[test]
+    fn test_drawn_bots_come_alive() {
+        let mut world = world(2, 1);
+        let config = config();
+        let bot = Object::Bot {
+            head: Direction::N,
+            color: Color32::RED,
+            links: Links::default(),
+        };
+        world.edit(&config, |map| {
+            map.get_mut(Point { x: 1, y: 0 }).unwrap().object = Some(bot)
+        });
+        let id = world.bot_at(Point { x: 1, y: 0 }).unwrap();
+        assert_eq!(world.bot(id).unwrap().energy, config.energy.max / 2);
+        world.edit(&config, |map| {
+            map.get_mut(Point { x: 1, y: 0 }).unwrap().object = Some(Object::Rock)
+        });
+        assert_eq!(world.population(), 0);
+    }
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        let config = SimConfig::default();
+        let size = Size { w: 16, h: 12 };
+        let generate =
+            |seed| World::generate(size, &config, Xoshiro256PlusPlus::seed_from_u64(seed));
+        let (mut a, mut b) = (generate(1), generate(1));
+        // More bots than cells, so all land is taken
+        let land = a.map().iter().filter(|(_, cell)| !cell.terrain.is_water());
+        assert_eq!(a.population(), land.count());
+        for _ in 0..50 {
+            a.step(&config);
+            b.step(&config);
+        }
+        assert_eq!(a, b);
+        assert_ne!(a, generate(2));
+    }
+
+    #[test]
+    fn test_rewind() {
+        let config = SimConfig::default();
+        let size = Size { w: 16, h: 12 };
+        let mut world = World::generate(size, &config, Xoshiro256PlusPlus::seed_from_u64(1));
+        let mut timeline = Timeline::new(10, 2);
+        let mut past = None;
+        while world.tick() < 30 {
+            timeline.record(world.tick(), &world);
+            world.step(&config);
+            if world.tick() == 25 {
+                past = Some(world.clone());
+            }
+        }
+        assert_eq!(timeline.rewind(25, |world| world.step(&config)), past);
+        assert_eq!(timeline.rewind(5, |world| world.step(&config)), None);
+    }
+
+    #[test]
+    fn test_serde() {
+        let config = SimConfig::default();
+        let mut world = World::generate(
+            Size { w: 8, h: 8 },
+            &config,
+            Xoshiro256PlusPlus::seed_from_u64(3),
+        );
+        world.step(&config);
+        let json = serde_json::to_value(&world).unwrap();
+        let mut loaded: World = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(loaded, world);
+        loaded.step(&config);
+        world.step(&config);
+        assert_eq!(loaded, world);
+
+        let mut moved = json;
+        let id = world.bots.keys().next().unwrap().to_string();
+        moved["bots"][&id]["position"] = serde_json::json!({ "x": 100, "y": 0 });
+        let error = serde_json::from_value::<World>(moved).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Bot {id} is not in a bot cell of its own")
+        );
+    }
+}